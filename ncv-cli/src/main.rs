@@ -1,29 +1,47 @@
 use neocuviz::{
-    cube::{Cube, CubeFace},
-    exporter::{Exporter, ExporterParameters, Fru, TopLayer},
-    notation::{Movement, Movements},
+    algorithm,
+    cube::{Cube, CubeFace, CubiePosition, OrientationTracking},
+    exporter::{
+        ClockFaces, Exporter, ExporterParameters, FtoNet, Fru, MegaminxNet, MegaminxTop,
+        PyraminxNet, SkewbFru, Square1Layers, TopLayer,
+    },
+    notation::Movement,
+    puzzle::{
+        Clock, ClockFace, Fto, FtoFace, Megaminx, MegaminxFace, Puzzle, Pyraminx, PyraminxFace,
+        Skewb, Square1,
+    },
+    scramble::Scramble,
+    solver::{Substep, SubstepParameters, SubstepSolver},
 };
 use std::{
     collections::HashMap,
     fs::File,
     io::{
-        prelude::*, stdin, stdout, BufReader, BufWriter, Error as IoError, ErrorKind,
+        prelude::*, stderr, stdin, stdout, BufReader, BufWriter, Error as IoError, ErrorKind,
         Result as IoResult,
     },
+    path::Path,
 };
 
 use clap::Clap;
 use image::{png::PNGEncoder, ColorType};
+use rand::{rngs::StdRng, SeedableRng};
 use usvg::{FitTo, Options, Tree};
 
 #[clap(version, author)]
 #[derive(Clap)]
 struct Arguments {
-    /// キューブのサイズ(分割数)
-    #[clap(short = "s", long, default_value = "3")]
-    cube_size: usize,
+    /// 対象のパズルを指定する
+    #[clap(short = "p", long, default_value = "cube", validator=is_valid_puzzle)]
+    puzzle: String,
+
+    /// キューブのサイズ(分割数)。
+    /// 直方体の場合は 2x2x3 のように 幅x奥行きx高さ で指定する
+    #[clap(short = "s", long, default_value = "3", validator=is_valid_size)]
+    cube_size: String,
 
-    /// 出力ファイルの視点(描画方法)を指定する
+    /// 出力ファイルの視点(描画方法)を指定する。
+    /// メガミンクスでは toplayer 以外は展開図になり、その他のパズルでは無視される
     #[clap(short = "v", long, default_value = "fru", validator=is_valid_view_type)]
     view_type: String,
 
@@ -35,10 +53,62 @@ struct Arguments {
     #[clap(short = "r", long, default_value = "512")]
     resolution: usize,
 
+    /// ステッカーの向きを表示する範囲を指定する
+    #[clap(short = "t", long, default_value = "none", validator=is_valid_tracking)]
+    track_orientation: String,
+
+    /// 融着するピースの組を 0,2,2+1,2,2 のように指定する。
+    /// 座標は L, D, B 面側を 0 とした (L-R, D-U, B-F) 方向のインデックス
+    #[clap(short = "b", long = "bandage", number_of_values = 1, validator=is_valid_bandage)]
+    bandages: Vec<String>,
+
     /// 入力の手順の逆手順を適用する
     #[clap(short = "i", long)]
     invert: bool,
 
+    /// 描画せず、手順を何回繰り返すと元に戻るかを出力する
+    #[clap(long)]
+    order: bool,
+
+    /// 描画せず、手順によるピースの巡回と向きの変化を出力する
+    #[clap(long)]
+    cycles: bool,
+
+    /// 描画せず、指定した回転記号列と効果が一致するかどうかを出力する。
+    /// AUF や全体回転で一致する場合はその調整も出力する
+    #[clap(long)]
+    compare: Option<String>,
+
+    /// 入力を読み込まず、スクランブルを生成して適用する。
+    /// 3x3x3 ではランダムステート、その他のサイズのキューブではランダムムーブで生成する。
+    /// 位置引数と --invert は指定できず、出力先は --output で指定する。
+    /// スクランブルは出力先が標準出力なら標準エラー出力に、それ以外では標準出力に書き出す
+    #[clap(long, conflicts_with_all = &["movements", "output", "invert"])]
+    scramble: bool,
+
+    /// スクランブルの生成に用いる乱数のシード
+    #[clap(long, requires = "scramble")]
+    seed: Option<u64>,
+
+    /// 3x3x3 キューブで途中の段階 (cross, xcross, eoline, eocross, pair) の最短手数の解を探す。
+    /// 解を標準出力に書き出し、各解を適用した状態を 出力先-1.svg のように番号を付けたファイルに描画する
+    #[clap(long, validator=is_valid_substep)]
+    substep: Option<String>,
+
+    /// 途中の段階を揃える面 (U, D, F, B, L, R)。
+    /// 省略された場合は D 面とする
+    #[clap(long, requires = "substep", validator=is_valid_substep_face)]
+    substep_face: Option<String>,
+
+    /// 途中の段階の解の前に、揃える面を D 面に移す全体回転を置く
+    #[clap(long, requires = "substep")]
+    pre_rotation: bool,
+
+    /// 途中の段階の解の最大数。
+    /// 省略された場合は 10 個とする
+    #[clap(long, requires = "substep")]
+    max_solutions: Option<usize>,
+
     /// 適用する回転記号列。
     /// 省略された場合は標準入力から読み込む
     movements: Option<String>,
@@ -46,6 +116,55 @@ struct Arguments {
     /// 出力先のファイル名。
     /// 省略された場合は標準出力に書き込む
     output: Option<String>,
+
+    /// 出力先のファイル名。位置引数の出力先の代わりに指定する
+    #[clap(short = "o", long = "output", conflicts_with = "output")]
+    output_file: Option<String>,
+}
+
+fn parse_size(value: &str) -> Option<(usize, usize, usize)> {
+    let sizes = value
+        .split('x')
+        .map(|s| s.parse().ok().filter(|&n| n > 0))
+        .collect::<Option<Vec<usize>>>()?;
+    match sizes[..] {
+        [n] => Some((n, n, n)),
+        [width, depth, height] => Some((width, height, depth)),
+        _ => None,
+    }
+}
+
+fn is_valid_size(value: &str) -> Result<(), String> {
+    match parse_size(value) {
+        Some(_) => Ok(()),
+        None => Err(format!("Invalid cube size: {}", value)),
+    }
+}
+
+fn parse_bandage(value: &str) -> Option<(CubiePosition, CubiePosition)> {
+    let parse_cubie = |s: &str| {
+        let indices = s
+            .split(',')
+            .map(|i| i.trim().parse().ok())
+            .collect::<Option<Vec<usize>>>()?;
+        match indices[..] {
+            [x, y, z] => Some((x, y, z)),
+            _ => None,
+        }
+    };
+    let mut cubies = value.split('+');
+    let pair = (parse_cubie(cubies.next()?)?, parse_cubie(cubies.next()?)?);
+    match cubies.next() {
+        Some(_) => None,
+        None => Some(pair),
+    }
+}
+
+fn is_valid_bandage(value: &str) -> Result<(), String> {
+    match parse_bandage(value) {
+        Some(_) => Ok(()),
+        None => Err(format!("Invalid bandage: {}", value)),
+    }
 }
 
 fn is_valid_format(value: &str) -> Result<(), String> {
@@ -55,6 +174,25 @@ fn is_valid_format(value: &str) -> Result<(), String> {
     }
 }
 
+/// キューブと同じ面を持つパズルの配色を返す。
+fn cube_colors() -> HashMap<CubeFace, String> {
+    let mut colors = HashMap::new();
+    colors.insert(CubeFace::Front, "#3f0".into());
+    colors.insert(CubeFace::Back, "#03c".into());
+    colors.insert(CubeFace::Left, "#f90".into());
+    colors.insert(CubeFace::Right, "#f30".into());
+    colors.insert(CubeFace::Up, "#fff".into());
+    colors.insert(CubeFace::Down, "#ff0".into());
+    colors
+}
+
+fn is_valid_puzzle(value: &str) -> Result<(), String> {
+    match value {
+        "cube" | "pyraminx" | "skewb" | "megaminx" | "square1" | "clock" | "fto" => Ok(()),
+        _ => Err(format!("Invalid puzzle: {}", value)),
+    }
+}
+
 fn is_valid_view_type(value: &str) -> Result<(), String> {
     match value {
         "fru" | "toplayer" => Ok(()),
@@ -62,85 +200,192 @@ fn is_valid_view_type(value: &str) -> Result<(), String> {
     }
 }
 
-fn main() -> IoResult<()> {
-    let args = Arguments::parse();
+fn is_valid_tracking(value: &str) -> Result<(), String> {
+    match value {
+        "none" | "centers" | "all" => Ok(()),
+        _ => Err(format!("Invalid orientation tracking: {}", value)),
+    }
+}
 
-    // 入力と出力
-    let (mut stdin_instance, mut stdout_instance);
-    let (mut infile, mut outfile);
-    let reader: &mut dyn Read = if let Some(filename) = &args.movements {
-        infile = BufReader::new(File::open(filename)?);
-        &mut infile
-    } else {
-        stdin_instance = BufReader::new(stdin());
-        &mut stdin_instance
-    };
-    let writer: &mut dyn Write = if let Some(filename) = &args.output {
-        outfile = BufWriter::new(File::create(filename)?);
-        &mut outfile
-    } else {
-        stdout_instance = BufWriter::new(stdout());
-        &mut stdout_instance
-    };
+fn parse_substep(value: &str) -> Option<Substep> {
+    match value {
+        "cross" => Some(Substep::Cross),
+        "xcross" => Some(Substep::XCross),
+        "eoline" => Some(Substep::EoLine),
+        "eocross" => Some(Substep::EoCross),
+        "pair" => Some(Substep::FirstPair),
+        _ => None,
+    }
+}
 
-    // キューブ操作
-    let mut cube = Cube::new(args.cube_size);
-    let mut movements_str = String::with_capacity(1024);
-    reader.read_to_string(&mut movements_str)?;
-
-    let mut movements = vec![];
-    let movements = Movements::new(&movements_str)
-        .try_fold(&mut movements, |movs, m| match m {
-            Ok(movement) => {
-                movs.push(movement);
-                Ok(movs)
-            }
-            Err(e) => Err(e),
-        })
-        .map_err(|e| IoError::new(ErrorKind::Other, e))?;
+fn is_valid_substep(value: &str) -> Result<(), String> {
+    match parse_substep(value) {
+        Some(_) => Ok(()),
+        None => Err(format!("Invalid substep: {}", value)),
+    }
+}
 
-    if args.invert {
-        for movement in Movement::inverse_sequence(movements.iter()) {
-            cube.apply(movement).unwrap();
-        }
+fn parse_substep_face(value: &str) -> Option<CubeFace> {
+    match value {
+        "U" => Some(CubeFace::Up),
+        "D" => Some(CubeFace::Down),
+        "F" => Some(CubeFace::Front),
+        "B" => Some(CubeFace::Back),
+        "L" => Some(CubeFace::Left),
+        "R" => Some(CubeFace::Right),
+        _ => None,
+    }
+}
+
+fn is_valid_substep_face(value: &str) -> Result<(), String> {
+    match parse_substep_face(value) {
+        Some(_) => Ok(()),
+        None => Err(format!("Invalid substep face: {}", value)),
+    }
+}
+
+/// 回転記号列をパースする。
+/// `invert` が指定された場合は逆手順を返す。
+fn parse_movements<P: Puzzle>(source: &str, invert: bool) -> IoResult<Vec<P::Movement>> {
+    let movements =
+        P::parse_movements(source).map_err(|e| IoError::new(ErrorKind::Other, e))?;
+    if invert {
+        Ok(movements
+            .into_iter()
+            .rev()
+            .map(P::inverse_movement)
+            .collect())
     } else {
-        for movement in movements {
-            cube.apply(*movement).unwrap();
-        }
+        Ok(movements)
     }
+}
 
+/// 手順を適用したパズルを描画し、SVG のソースを返す。
+fn render<P: Puzzle>(
+    mut puzzle: P,
+    movements: &[P::Movement],
+    exporter: &mut dyn Exporter<P>,
+    params: &ExporterParameters<P::Face>,
+) -> IoResult<Vec<u8>> {
+    for &movement in movements {
+        puzzle
+            .apply_movement(movement)
+            .map_err(|e| IoError::new(ErrorKind::Other, e))?;
+    }
 
-    // 描画
-    let mut exporter: Box<dyn Exporter> = match &args.view_type[..] {
-        "fru" => Box::new(Fru::default()),
-        "toplayer" => Box::new(TopLayer::default()),
+    exporter.set_params(params);
+    let mut svg_src = Vec::with_capacity(8192);
+    exporter.write(&puzzle, &mut svg_src)?;
+    Ok(svg_src)
+}
+
+/// 引数で指定されたキューブを生成する。
+fn build_cube(args: &Arguments) -> IoResult<Cube> {
+    let (width, height, depth) = parse_size(&args.cube_size).expect("Size should be validated");
+    let mut cube = Cube::cuboid(width, height, depth);
+    cube.set_orientation_tracking(match &args.track_orientation[..] {
+        "none" => OrientationTracking::Disabled,
+        "centers" => OrientationTracking::Centers,
+        "all" => OrientationTracking::All,
         _ => unreachable!(),
+    });
+    for bandage in &args.bandages {
+        let (a, b) = parse_bandage(bandage).expect("Bandage should be validated");
+        cube.add_bandage(a, b)
+            .map_err(|e| IoError::new(ErrorKind::Other, e))?;
+    }
+    Ok(cube)
+}
+
+/// 解析モードが指定されていれば、手順の解析結果を書き出して `true` を返す。
+fn write_cube_analysis(
+    args: &Arguments,
+    cube: &Cube,
+    movements: &[Movement],
+    writer: &mut dyn Write,
+) -> IoResult<bool> {
+    if args.order {
+        let order =
+            algorithm::order(cube, movements).map_err(|e| IoError::new(ErrorKind::Other, e))?;
+        writeln!(writer, "{}", order)?;
+        return Ok(true);
+    }
+
+    if args.cycles {
+        let analysis = algorithm::analyze(cube, movements)
+            .map_err(|e| IoError::new(ErrorKind::Other, e))?;
+        write!(writer, "{}", analysis)?;
+        return Ok(true);
+    }
+
+    if let Some(other) = &args.compare {
+        let other = parse_movements::<Cube>(other, false)?;
+        let equivalence = algorithm::compare(cube, movements, &other)
+            .map_err(|e| IoError::new(ErrorKind::Other, e))?;
+        writeln!(writer, "{}", equivalence)?;
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+/// 途中の段階の解を探し、解を書き出して各解を適用した状態を番号付きのファイルに描画する。
+fn write_substep_solutions(
+    args: &Arguments,
+    cube: &Cube,
+    movements: &[Movement],
+    output: &str,
+    exporter: &mut dyn Exporter<Cube>,
+    params: &ExporterParameters<CubeFace>,
+) -> IoResult<()> {
+    let substep = parse_substep(args.substep.as_ref().expect("Substep should be specified"))
+        .expect("Substep should be validated");
+    let parameters = SubstepParameters {
+        face: parse_substep_face(args.substep_face.as_deref().unwrap_or("D"))
+            .expect("Face should be validated"),
+        pre_rotation: args.pre_rotation,
+        limit: args.max_solutions.unwrap_or(10),
     };
-    let params = ExporterParameters {
-        colors: {
-            let mut colors = HashMap::new();
-            colors.insert(CubeFace::Front, "#3f0".into());
-            colors.insert(CubeFace::Back, "#03c".into());
-            colors.insert(CubeFace::Left, "#f90".into());
-            colors.insert(CubeFace::Right, "#f30".into());
-            colors.insert(CubeFace::Up, "#fff".into());
-            colors.insert(CubeFace::Down, "#ff0".into());
-            colors
-        },
-        size: args.resolution as f64,
-    };
-    exporter.set_params(&params);
+    let mut scrambled = cube.clone();
+    for &movement in movements {
+        scrambled
+            .apply_movement(movement)
+            .map_err(|e| IoError::new(ErrorKind::Other, e))?;
+    }
+    let solutions = SubstepSolver::new(substep)
+        .solve(&scrambled, &parameters)
+        .map_err(|e| IoError::new(ErrorKind::Other, e))?;
 
-    let mut svg_src = Vec::with_capacity(8192);
-    exporter.write(&cube, &mut svg_src).unwrap();
+    let output = Path::new(output);
+    let stem = output
+        .file_stem()
+        .ok_or_else(|| IoError::new(ErrorKind::Other, "Output must be a file name"))?;
+    for (i, solution) in solutions.iter().enumerate() {
+        let notation: Vec<_> = solution.iter().map(|m| m.to_string()).collect();
+        writeln!(stdout(), "{}", notation.join(" "))?;
 
-    match &args.output_format[..] {
+        let svg_src = render(scrambled.clone(), solution, exporter, params)?;
+        let mut filename = stem.to_os_string();
+        filename.push(format!("-{}", i + 1));
+        if let Some(extension) = output.extension() {
+            filename.push(".");
+            filename.push(extension);
+        }
+        let mut writer = BufWriter::new(File::create(output.with_file_name(filename))?);
+        write_image(&svg_src, &args.output_format, &mut writer)?;
+    }
+    Ok(())
+}
+
+/// SVG のソースを指定されたフォーマットで書き出す。
+fn write_image(svg_src: &[u8], format: &str, writer: &mut dyn Write) -> IoResult<()> {
+    match format {
         "svg" => {
-            writer.write_all(&svg_src)?;
+            writer.write_all(svg_src)?;
         }
         "png" => {
             let options = Options::default();
-            let tree = Tree::from_data(&svg_src, &options).expect("Valid SVG should be generated");
+            let tree = Tree::from_data(svg_src, &options).expect("Valid SVG should be generated");
             let img = resvg::render(&tree, FitTo::Original, None).unwrap();
 
             let (w, h) = (img.width(), img.height());
@@ -153,6 +398,248 @@ fn main() -> IoResult<()> {
         }
         _ => unreachable!(),
     }
-
     Ok(())
 }
+
+/// 引数で指定されたキューブのスクランブルを生成する。
+fn generate_scramble(args: &Arguments) -> IoResult<Scramble> {
+    let divisions = match parse_size(&args.cube_size) {
+        Some((width, height, depth)) if width == height && height == depth => width,
+        _ => 0,
+    };
+    if args.puzzle != "cube" || divisions < 2 {
+        return Err(IoError::new(
+            ErrorKind::Other,
+            "Scramble is only supported for cubes",
+        ));
+    }
+    let mut rng = match args.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    if divisions == 3 {
+        Ok(Scramble::random_state(&mut rng))
+    } else {
+        Ok(Scramble::random_moves(divisions, &mut rng))
+    }
+}
+
+fn main() -> IoResult<()> {
+    let args = Arguments::parse();
+
+    let scramble = if args.scramble {
+        Some(generate_scramble(&args)?)
+    } else {
+        None
+    };
+    let input = args.movements.as_ref();
+    let output = args.output_file.as_ref().or_else(|| args.output.as_ref());
+
+    if args.substep.is_some() {
+        if args.puzzle != "cube" {
+            return Err(IoError::new(
+                ErrorKind::Other,
+                "Substep is only supported for cubes",
+            ));
+        }
+        if output.is_none() {
+            return Err(IoError::new(
+                ErrorKind::Other,
+                "Output file is required with --substep",
+            ));
+        }
+    }
+
+    // 入力と出力 (途中の段階の探索では解ごとにファイルを作成する)
+    let (mut stdin_instance, mut stdout_instance);
+    let (mut infile, mut outfile);
+    let writer: &mut dyn Write = if let (Some(filename), None) = (output, &args.substep) {
+        outfile = BufWriter::new(File::create(filename)?);
+        &mut outfile
+    } else {
+        stdout_instance = BufWriter::new(stdout());
+        &mut stdout_instance
+    };
+
+    let movements_str = if let Some(scramble) = &scramble {
+        if output.is_some() {
+            writeln!(stdout(), "{}", scramble)?;
+        } else {
+            writeln!(stderr(), "{}", scramble)?;
+        }
+        scramble.to_string()
+    } else {
+        let reader: &mut dyn Read = if let Some(filename) = input {
+            infile = BufReader::new(File::open(filename)?);
+            &mut infile
+        } else {
+            stdin_instance = BufReader::new(stdin());
+            &mut stdin_instance
+        };
+        let mut movements_str = String::with_capacity(1024);
+        reader.read_to_string(&mut movements_str)?;
+        movements_str
+    };
+
+    let is_analysis = args.order || args.cycles || args.compare.is_some();
+    if is_analysis && args.puzzle != "cube" {
+        return Err(IoError::new(
+            ErrorKind::Other,
+            "Analysis is only supported for cubes",
+        ));
+    }
+
+    // パズル操作と描画
+    let svg_src = match &args.puzzle[..] {
+        "cube" => {
+            let cube = build_cube(&args)?;
+            let movements = parse_movements::<Cube>(&movements_str, args.invert)?;
+            if write_cube_analysis(&args, &cube, &movements, writer)? {
+                return Ok(());
+            }
+
+            let mut exporter: Box<dyn Exporter<Cube>> = match &args.view_type[..] {
+                "fru" => Box::new(Fru::default()),
+                "toplayer" => Box::new(TopLayer::default()),
+                _ => unreachable!(),
+            };
+            let params = ExporterParameters {
+                colors: cube_colors(),
+                unknown_color: "#888".into(),
+                size: args.resolution as f64,
+            };
+            if let Some(output) = output {
+                if args.substep.is_some() {
+                    return write_substep_solutions(
+                        &args,
+                        &cube,
+                        &movements,
+                        output,
+                        exporter.as_mut(),
+                        &params,
+                    );
+                }
+            }
+            render(cube, &movements, exporter.as_mut(), &params)?
+        }
+        "pyraminx" => {
+            let movements = parse_movements::<Pyraminx>(&movements_str, args.invert)?;
+            let params = ExporterParameters {
+                colors: {
+                    let mut colors = HashMap::new();
+                    colors.insert(PyraminxFace::Front, "#3f0".into());
+                    colors.insert(PyraminxFace::Left, "#f30".into());
+                    colors.insert(PyraminxFace::Right, "#03c".into());
+                    colors.insert(PyraminxFace::Down, "#ff0".into());
+                    colors
+                },
+                unknown_color: "#888".into(),
+                size: args.resolution as f64,
+            };
+            render(
+                Pyraminx::new(),
+                &movements,
+                &mut PyraminxNet::default(),
+                &params,
+            )?
+        }
+        "skewb" => {
+            let movements = parse_movements::<Skewb>(&movements_str, args.invert)?;
+            let params = ExporterParameters {
+                colors: cube_colors(),
+                unknown_color: "#888".into(),
+                size: args.resolution as f64,
+            };
+            render(
+                Skewb::new(),
+                &movements,
+                &mut SkewbFru::default(),
+                &params,
+            )?
+        }
+        "megaminx" => {
+            let movements = parse_movements::<Megaminx>(&movements_str, args.invert)?;
+            let mut exporter: Box<dyn Exporter<Megaminx>> = match &args.view_type[..] {
+                "toplayer" => Box::new(MegaminxTop::default()),
+                _ => Box::new(MegaminxNet::default()),
+            };
+            let params = ExporterParameters {
+                colors: {
+                    let mut colors = HashMap::new();
+                    colors.insert(MegaminxFace::Up, "#fff".into());
+                    colors.insert(MegaminxFace::Front, "#3f0".into());
+                    colors.insert(MegaminxFace::Right, "#f30".into());
+                    colors.insert(MegaminxFace::BackRight, "#03c".into());
+                    colors.insert(MegaminxFace::BackLeft, "#ff0".into());
+                    colors.insert(MegaminxFace::Left, "#90c".into());
+                    colors.insert(MegaminxFace::DownRight, "#ffc".into());
+                    colors.insert(MegaminxFace::DownBackRight, "#f9c".into());
+                    colors.insert(MegaminxFace::Back, "#9f6".into());
+                    colors.insert(MegaminxFace::DownBackLeft, "#f90".into());
+                    colors.insert(MegaminxFace::DownLeft, "#6cf".into());
+                    colors.insert(MegaminxFace::Down, "#999".into());
+                    colors
+                },
+                unknown_color: "#888".into(),
+                size: args.resolution as f64,
+            };
+            render(Megaminx::new(), &movements, exporter.as_mut(), &params)?
+        }
+        "square1" => {
+            let movements = parse_movements::<Square1>(&movements_str, args.invert)?;
+            let params = ExporterParameters {
+                colors: cube_colors(),
+                unknown_color: "#888".into(),
+                size: args.resolution as f64,
+            };
+            render(
+                Square1::new(),
+                &movements,
+                &mut Square1Layers::default(),
+                &params,
+            )?
+        }
+        "clock" => {
+            let movements = parse_movements::<Clock>(&movements_str, args.invert)?;
+            let params = ExporterParameters {
+                colors: {
+                    let mut colors = HashMap::new();
+                    colors.insert(ClockFace::Front, "#036".into());
+                    colors.insert(ClockFace::Back, "#6cf".into());
+                    colors
+                },
+                unknown_color: "#888".into(),
+                size: args.resolution as f64,
+            };
+            render(
+                Clock::new(),
+                &movements,
+                &mut ClockFaces::default(),
+                &params,
+            )?
+        }
+        "fto" => {
+            let movements = parse_movements::<Fto>(&movements_str, args.invert)?;
+            let params = ExporterParameters {
+                colors: {
+                    let mut colors = HashMap::new();
+                    colors.insert(FtoFace::Up, "#fff".into());
+                    colors.insert(FtoFace::Front, "#3f0".into());
+                    colors.insert(FtoFace::Right, "#f30".into());
+                    colors.insert(FtoFace::Left, "#90c".into());
+                    colors.insert(FtoFace::Back, "#03c".into());
+                    colors.insert(FtoFace::Down, "#ff0".into());
+                    colors.insert(FtoFace::BackRight, "#f90".into());
+                    colors.insert(FtoFace::BackLeft, "#999".into());
+                    colors
+                },
+                unknown_color: "#888".into(),
+                size: args.resolution as f64,
+            };
+            render(Fto::new(), &movements, &mut FtoNet::default(), &params)?
+        }
+        _ => unreachable!(),
+    };
+
+    write_image(&svg_src, &args.output_format, writer)
+}
@@ -0,0 +1,732 @@
+use crate::{
+    cube::{Cube, CubeError, CubeFace, CubiePosition, Sticker},
+    notation::{Face as MovementFace, Movement, Rotation as MovementRotation},
+};
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::fmt::{Display, Formatter, Result as FmtResult};
+
+/// ステッカーの位置 (面, 行, 列) を表す。
+pub type StickerLocation = (CubeFace, usize, usize);
+
+/// 手順によるステッカーの置換を表す。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StickerPermutation {
+    /// 手順を適用する前の状態
+    cube: Cube,
+
+    /// インデックスに対応するステッカーの位置
+    locations: Vec<StickerLocation>,
+
+    /// 各位置のステッカーが移動する先のインデックス
+    targets: Vec<usize>,
+
+    /// 移動した時に変化する向き
+    twists: Vec<u8>,
+}
+
+impl StickerPermutation {
+    /// 手順を 1 回適用した時のステッカーの置換を求める。
+    pub fn new(cube: &Cube, movements: &[Movement]) -> Result<StickerPermutation, CubeError> {
+        let locations: Vec<_> = CubeFace::ALL
+            .iter()
+            .flat_map(|&face| {
+                let (rows, columns) = cube.face_size(face);
+                (0..rows).flat_map(move |row| (0..columns).map(move |column| (face, row, column)))
+            })
+            .collect();
+
+        // 全てのステッカーに位置を表すラベルを貼って手順を適用する
+        let mut labeled = cube.clone();
+        for (i, &(face, row, column)) in locations.iter().enumerate() {
+            labeled.set_sticker(face, row, column, Sticker::Custom(i.to_string()))?;
+            labeled.set_orientation(face, row, column, 0)?;
+        }
+        for &movement in movements {
+            labeled.apply(movement)?;
+        }
+
+        let mut targets = vec![0; locations.len()];
+        let mut twists = vec![0; locations.len()];
+        for (i, &(face, row, column)) in locations.iter().enumerate() {
+            let source = match labeled.sticker(face, row, column) {
+                Some(Sticker::Custom(label)) => label.parse::<usize>().ok(),
+                _ => None,
+            }
+            .expect("Every sticker should be labeled");
+            targets[source] = i;
+            twists[source] = labeled.orientation(face, row, column).unwrap_or(0);
+        }
+
+        Ok(StickerPermutation {
+            cube: cube.clone(),
+            locations,
+            targets,
+            twists,
+        })
+    }
+
+    /// 移動するステッカーの巡回を、各巡回の移動順の位置の列として返す。
+    pub fn cycles(&self) -> Vec<Vec<StickerLocation>> {
+        self.index_cycles()
+            .into_iter()
+            .filter(|cycle| cycle.len() > 1)
+            .map(|cycle| cycle.into_iter().map(|i| self.locations[i]).collect())
+            .collect()
+    }
+
+    /// 手順を何回繰り返すと元の状態に戻るかを返す。
+    /// 同じ色で向きも区別できないステッカー同士の入れ替えは元に戻ったものとみなす。
+    /// 融着されたピースのステッカーは色に関わらず区別する。
+    pub fn order(&self) -> usize {
+        self.index_cycles()
+            .iter()
+            .map(|cycle| self.cycle_order(cycle))
+            .fold(1, lcm)
+    }
+
+    /// ピース単位の巡回と向きの変化を解析する。
+    pub fn analyze(&self) -> CycleAnalysis {
+        // ピースごとにステッカーをまとめ、向きの基準となる面から順に並べる
+        let mut cubie_stickers: BTreeMap<CubiePosition, Vec<usize>> = BTreeMap::new();
+        for (i, &(face, row, column)) in self.locations.iter().enumerate() {
+            let cubie = self
+                .cube
+                .sticker_cubie(face, row, column)
+                .expect("Every sticker should belong to a cubie");
+            cubie_stickers.entry(cubie).or_default().push(i);
+        }
+
+        let mut pieces = vec![];
+        let mut piece_stickers = vec![];
+        let mut sticker_piece = vec![(0, 0); self.locations.len()];
+        for (cubie, stickers) in cubie_stickers {
+            let faces = piece_faces(stickers.iter().map(|&i| self.locations[i].0).collect());
+            let stickers: Vec<_> = faces
+                .iter()
+                .map(|&face| {
+                    *stickers
+                        .iter()
+                        .find(|&&i| self.locations[i].0 == face)
+                        .expect("Face should be taken from stickers")
+                })
+                .collect();
+            for (position, &sticker) in stickers.iter().enumerate() {
+                sticker_piece[sticker] = (pieces.len(), position);
+            }
+
+            let index = self.piece_index(cubie, &faces, self.locations[stickers[0]]);
+            pieces.push(Piece {
+                cubie,
+                faces,
+                index,
+            });
+            piece_stickers.push(stickers);
+        }
+
+        // 基準のステッカーの移動先からピースの移動先と向きの変化を求める
+        let moves: Vec<_> = piece_stickers
+            .iter()
+            .map(|stickers| sticker_piece[self.targets[stickers[0]]])
+            .collect();
+
+        let mut corners = vec![];
+        let mut edges = vec![];
+        let mut centers = vec![];
+        let mut visited = vec![false; pieces.len()];
+        for start in 0..pieces.len() {
+            if visited[start] {
+                continue;
+            }
+            let mut cycle = vec![];
+            let mut twist = 0;
+            let mut current = start;
+            while !visited[current] {
+                visited[current] = true;
+                cycle.push(pieces[current].clone());
+                twist += moves[current].1;
+                current = moves[current].0;
+            }
+
+            let twist = (twist % pieces[start].faces.len()) as u8;
+            if cycle.len() == 1 && twist == 0 {
+                continue;
+            }
+            let cycle = PieceCycle {
+                pieces: cycle,
+                twist,
+            };
+            match pieces[start].kind() {
+                PieceKind::Corner => corners.push(cycle),
+                PieceKind::Edge => edges.push(cycle),
+                PieceKind::Center => centers.push(cycle),
+            }
+        }
+
+        let reoriented = pieces
+            .iter()
+            .zip(moves.iter())
+            .filter(|(_, &(_, twist))| twist != 0)
+            .map(|(piece, &(_, twist))| (piece.clone(), twist as u8))
+            .collect();
+
+        let mut fixed_stickers = vec![];
+        let mut affected_cubies = vec![];
+        for (i, &location) in self.locations.iter().enumerate() {
+            if self.targets[i] == i && self.twists[i] == 0 {
+                fixed_stickers.push(location);
+            } else {
+                let cubie = pieces[sticker_piece[i].0].cubie;
+                if !affected_cubies.contains(&cubie) {
+                    affected_cubies.push(cubie);
+                }
+            }
+        }
+
+        CycleAnalysis {
+            dimensions: self.cube.dimensions(),
+            corners,
+            edges,
+            centers,
+            reoriented,
+            fixed_stickers,
+            affected_cubies,
+        }
+    }
+
+    /// 同じ面の組を持つピースが複数ある場合に、区別するための添字を求める。
+    /// エッジは辺に沿った方向の位置、センターはステッカーの (行, 列) を用いる。
+    fn piece_index(
+        &self,
+        (x, y, z): CubiePosition,
+        faces: &[CubeFace],
+        (face, row, column): StickerLocation,
+    ) -> Vec<usize> {
+        let (width, height, depth) = self.cube.dimensions();
+        match faces.len() {
+            1 => {
+                let (rows, columns) = self.cube.face_size(face);
+                if rows.saturating_sub(2) * columns.saturating_sub(2) > 1 {
+                    vec![row, column]
+                } else {
+                    vec![]
+                }
+            }
+            2 => {
+                let axes: Vec<_> = faces.iter().map(|&f| face_axis(f)).collect();
+                let (position, length) = match (0..3).find(|a| !axes.contains(a)) {
+                    Some(0) => (x, width),
+                    Some(1) => (y, height),
+                    Some(_) => (z, depth),
+                    None => return vec![],
+                };
+                if length > 3 {
+                    vec![position]
+                } else {
+                    vec![]
+                }
+            }
+            _ => vec![],
+        }
+    }
+
+    /// 全ての巡回を長さ 1 のものも含めて返す。
+    fn index_cycles(&self) -> Vec<Vec<usize>> {
+        let mut visited = vec![false; self.targets.len()];
+        let mut cycles = vec![];
+        for start in 0..self.targets.len() {
+            if visited[start] {
+                continue;
+            }
+            let mut cycle = vec![];
+            let mut current = start;
+            while !visited[current] {
+                visited[current] = true;
+                cycle.push(current);
+                current = self.targets[current];
+            }
+            cycles.push(cycle);
+        }
+        cycles
+    }
+
+    /// 1 つの巡回が元の状態に戻るまでの回数を返す。
+    /// 向きの変化があるため、巡回の長さの 4 倍までに必ず戻る。
+    fn cycle_order(&self, cycle: &[usize]) -> usize {
+        let length = cycle.len();
+        let sticker = |i: usize| {
+            let (face, row, column) = self.locations[i];
+            (
+                self.cube.sticker(face, row, column),
+                self.cube.orientation(face, row, column),
+                self.cube.sticker_cubie(face, row, column),
+            )
+        };
+        let states: Vec<_> = cycle.iter().map(|&i| sticker(i)).collect();
+
+        (1..=length * 4)
+            .find(|&count| {
+                (0..length).all(|start| {
+                    let end = (start + count) % length;
+                    let (sticker, orientation, cubie) = states[start];
+                    let (end_sticker, end_orientation, end_cubie) = states[end];
+
+                    let bandaged = cubie.map(|c| self.cube.is_bandaged(c)) == Some(true)
+                        || end_cubie.map(|c| self.cube.is_bandaged(c)) == Some(true);
+                    if bandaged && count % length != 0 {
+                        return false;
+                    }
+
+                    let twist: usize = (start..start + count)
+                        .map(|i| self.twists[cycle[i % length]] as usize)
+                        .sum();
+                    let orientation = orientation.map(|o| (o as usize + twist) % 4);
+                    sticker == end_sticker && orientation == end_orientation.map(usize::from)
+                })
+            })
+            .expect("Every cycle should return within four rounds")
+    }
+}
+
+/// ピースの種類を表す。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PieceKind {
+    /// コーナー
+    Corner,
+
+    /// エッジ
+    Edge,
+
+    /// センター
+    Center,
+}
+
+/// 解析結果に現れるピースを表す。
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Piece {
+    /// ピースの元の位置
+    pub cubie: CubiePosition,
+
+    /// ステッカーが貼られている面。
+    /// 先頭が向きの基準となる面で、コーナーは Singmaster 記法の順になる
+    pub faces: Vec<CubeFace>,
+
+    /// 同じ面の組を持つピースが複数ある場合に区別するための添字
+    pub index: Vec<usize>,
+}
+
+impl Piece {
+    /// ピースの種類を返す。
+    pub fn kind(&self) -> PieceKind {
+        match self.faces.len() {
+            0 | 1 => PieceKind::Center,
+            2 => PieceKind::Edge,
+            _ => PieceKind::Corner,
+        }
+    }
+}
+
+impl Display for Piece {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        for face in &self.faces {
+            write!(f, "{}", face)?;
+        }
+        if !self.index.is_empty() {
+            let index: Vec<_> = self.index.iter().map(|i| i.to_string()).collect();
+            write!(f, "[{}]", index.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/// ピースの巡回を表す。
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PieceCycle {
+    /// 移動する順に並べたピース
+    pub pieces: Vec<Piece>,
+
+    /// 1 周した時の向きの変化。
+    /// コーナーは 1 が反時計回り、2 が時計回りで、エッジは 1 が反転を表す
+    pub twist: u8,
+}
+
+impl Display for PieceCycle {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let names: Vec<_> = self.pieces.iter().map(|p| p.to_string()).collect();
+        write!(f, "({})", names.join(" "))?;
+        match self.pieces.first() {
+            Some(piece) => write!(f, "{}", twist_suffix(piece.kind(), self.twist)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// 手順がピースに与える影響の解析結果を表す。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleAnalysis {
+    dimensions: (usize, usize, usize),
+    corners: Vec<PieceCycle>,
+    edges: Vec<PieceCycle>,
+    centers: Vec<PieceCycle>,
+    reoriented: Vec<(Piece, u8)>,
+    fixed_stickers: Vec<StickerLocation>,
+    affected_cubies: Vec<CubiePosition>,
+}
+
+impl CycleAnalysis {
+    /// 手順を 1 回適用した時の影響を解析する。
+    pub fn new(cube: &Cube, movements: &[Movement]) -> Result<CycleAnalysis, CubeError> {
+        Ok(StickerPermutation::new(cube, movements)?.analyze())
+    }
+
+    /// コーナーの巡回を返す。その場でねじれるだけのものも長さ 1 の巡回として含む。
+    pub fn corners(&self) -> &[PieceCycle] {
+        &self.corners
+    }
+
+    /// エッジの巡回を返す。その場で反転するだけのものも長さ 1 の巡回として含む。
+    pub fn edges(&self) -> &[PieceCycle] {
+        &self.edges
+    }
+
+    /// センターの巡回を返す。
+    pub fn centers(&self) -> &[PieceCycle] {
+        &self.centers
+    }
+
+    /// 向きが変化するピースを、変化量と組にして返す。
+    /// 向きは U, D 面 (エッジでそれがない場合は F, B 面) のステッカーを基準とする。
+    pub fn reoriented(&self) -> &[(Piece, u8)] {
+        &self.reoriented
+    }
+
+    /// 位置も向きも変化しないステッカーを返す。
+    pub fn fixed_stickers(&self) -> &[StickerLocation] {
+        &self.fixed_stickers
+    }
+
+    /// 位置か向きが変化するステッカーを持つピースの位置を返す。
+    pub fn affected_cubies(&self) -> &[CubiePosition] {
+        &self.affected_cubies
+    }
+
+    /// 手順が何も変化させないかどうかを返す。
+    pub fn is_identity(&self) -> bool {
+        self.affected_cubies.is_empty()
+    }
+
+    /// 変化するピースが全て指定した面の層に含まれるかどうかを返す。
+    /// 例えば `CubeFace::Up` に対して `true` であれば F2L を崩さない。
+    pub fn affects_only_layer(&self, face: CubeFace) -> bool {
+        let (width, height, depth) = self.dimensions;
+        self.affected_cubies.iter().all(|&(x, y, z)| match face {
+            CubeFace::Front => z + 1 == depth,
+            CubeFace::Back => z == 0,
+            CubeFace::Left => x == 0,
+            CubeFace::Right => x + 1 == width,
+            CubeFace::Up => y + 1 == height,
+            CubeFace::Down => y == 0,
+        })
+    }
+}
+
+impl Display for CycleAnalysis {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let join = |items: Vec<String>| {
+            if items.is_empty() {
+                "-".to_string()
+            } else {
+                items.join(" ")
+            }
+        };
+        let cycles = |cycles: &[PieceCycle]| join(cycles.iter().map(|c| c.to_string()).collect());
+
+        writeln!(f, "corners: {}", cycles(&self.corners))?;
+        writeln!(f, "edges: {}", cycles(&self.edges))?;
+        writeln!(f, "centers: {}", cycles(&self.centers))?;
+        writeln!(
+            f,
+            "reoriented: {}",
+            join(
+                self.reoriented
+                    .iter()
+                    .map(|(p, t)| format!("{}{}", p, twist_suffix(p.kind(), *t)))
+                    .collect()
+            )
+        )?;
+        writeln!(
+            f,
+            "fixed: {}",
+            join(
+                self.fixed_stickers
+                    .iter()
+                    .map(|(face, row, column)| format!("{}[{},{}]", face, row, column))
+                    .collect()
+            )
+        )
+    }
+}
+
+/// 手順がピースに与える影響を解析する。
+pub fn analyze(cube: &Cube, movements: &[Movement]) -> Result<CycleAnalysis, CubeError> {
+    CycleAnalysis::new(cube, movements)
+}
+
+/// 2 つの手順の効果を比較した結果を表す。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Equivalence {
+    /// 完全に一致する
+    Identical,
+
+    /// 1 つ目の手順の前後に U 面の回転 (AUF) を加えると一致する
+    Auf {
+        /// 手順の前に加える回転
+        pre: Option<Movement>,
+
+        /// 手順の後に加える回転
+        post: Option<Movement>,
+    },
+
+    /// 1 つ目の手順の後に全体回転を加えると一致する
+    Rotation(Vec<Movement>),
+
+    /// 1 つ目の手順の前後に AUF を加え、さらに全体回転を加えると一致する
+    AufRotation {
+        /// 手順の前に加える回転
+        pre: Option<Movement>,
+
+        /// 手順の後に加える回転
+        post: Option<Movement>,
+
+        /// 最後に加える全体回転
+        rotations: Vec<Movement>,
+    },
+
+    /// 全体回転で持ち替えてから 1 つ目の手順を行い、持ち替えを戻すと一致する。
+    /// 持ち替えた向きの別のケースを解くため、同じケースを解く手順とはみなさない
+    Conjugate(Vec<Movement>),
+
+    /// 一致しない
+    Different,
+}
+
+impl Equivalence {
+    /// AUF と全体回転の調整で同じケースを解くかどうかを返す。
+    pub fn is_equivalent(&self) -> bool {
+        !matches!(self, Equivalence::Conjugate(_) | Equivalence::Different)
+    }
+}
+
+impl Display for Equivalence {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Equivalence::Identical => write!(f, "identical"),
+            Equivalence::Auf { pre, post } => {
+                write!(f, "identical up to AUF")?;
+                if let Some(pre) = pre {
+                    write!(f, " (pre: {})", pre)?;
+                }
+                if let Some(post) = post {
+                    write!(f, " (post: {})", post)?;
+                }
+                Ok(())
+            }
+            Equivalence::Rotation(rotations) => {
+                let rotations: Vec<_> = rotations.iter().map(|m| m.to_string()).collect();
+                write!(f, "identical up to rotation ({})", rotations.join(" "))
+            }
+            Equivalence::AufRotation {
+                pre,
+                post,
+                rotations,
+            } => {
+                write!(f, "identical up to AUF and rotation")?;
+                if let Some(pre) = pre {
+                    write!(f, " (pre: {})", pre)?;
+                }
+                if let Some(post) = post {
+                    write!(f, " (post: {})", post)?;
+                }
+                let rotations: Vec<_> = rotations.iter().map(|m| m.to_string()).collect();
+                write!(f, " (rotation: {})", rotations.join(" "))
+            }
+            Equivalence::Conjugate(rotations) => {
+                let rotations: Vec<_> = rotations.iter().map(|m| m.to_string()).collect();
+                write!(
+                    f,
+                    "identical when performed after ({})",
+                    rotations.join(" ")
+                )
+            }
+            Equivalence::Different => write!(f, "different"),
+        }
+    }
+}
+
+/// 2 つの手順を同じ状態に適用した時の効果を比較する。
+/// 完全一致、AUF による一致、全体回転による一致、AUF と全体回転による一致、
+/// 持ち替えによる一致の順に調べ、最初に見つかった調整を返す。
+pub fn compare(
+    cube: &Cube,
+    first: &[Movement],
+    second: &[Movement],
+) -> Result<Equivalence, CubeError> {
+    let apply = |movements: &[Movement]| -> Result<Cube, CubeError> {
+        let mut cube = cube.clone();
+        for &movement in movements {
+            cube.apply(movement)?;
+        }
+        Ok(cube)
+    };
+    let first_result = apply(first)?;
+    let second_result = apply(second)?;
+    if first_result == second_result {
+        return Ok(Equivalence::Identical);
+    }
+
+    // 前後どちらか一方のみで済むものを優先する
+    let aufs = [
+        None,
+        Some(MovementRotation::Clockwise),
+        Some(MovementRotation::Turnover),
+        Some(MovementRotation::Counterclockwise),
+    ];
+    let mut candidates: Vec<_> = aufs
+        .iter()
+        .flat_map(|&pre| aufs.iter().map(move |&post| (pre, post)))
+        .filter(|&(pre, post)| pre.is_some() || post.is_some())
+        .collect();
+    candidates.sort_by_key(|&(pre, post)| pre.is_some() && post.is_some());
+
+    let auf = |direction| Movement {
+        target: MovementFace::Up(1),
+        direction,
+    };
+    // 直方体などで AUF が適用できない場合は候補から外す
+    let with_aufs: Vec<_> = candidates
+        .into_iter()
+        .filter_map(|(pre, post)| {
+            let (pre, post) = (pre.map(auf), post.map(auf));
+            let movements: Vec<_> = pre
+                .iter()
+                .chain(first.iter())
+                .chain(post.iter())
+                .cloned()
+                .collect();
+            apply(&movements).ok().map(|result| (pre, post, result))
+        })
+        .collect();
+    for (pre, post, result) in &with_aufs {
+        if *result == second_result {
+            return Ok(Equivalence::Auf {
+                pre: *pre,
+                post: *post,
+            });
+        }
+    }
+
+    if let Some(rotations) = first_result.rotation_to(&second_result) {
+        return Ok(Equivalence::Rotation(rotations));
+    }
+
+    // y などで持ち替えた手順は、AUF を加えた上で向きだけが異なることが多い
+    for (pre, post, result) in with_aufs {
+        if let Some(rotations) = result.rotation_to(&second_result) {
+            return Ok(Equivalence::AufRotation {
+                pre,
+                post,
+                rotations,
+            });
+        }
+    }
+
+    for (rotations, _) in cube.rotated_variants() {
+        if rotations.is_empty() {
+            continue;
+        }
+        let movements: Vec<_> = rotations
+            .iter()
+            .chain(first.iter())
+            .cloned()
+            .chain(Movement::inverse_sequence(rotations.iter()))
+            .collect();
+        if apply(&movements).ok().as_ref() == Some(&second_result) {
+            return Ok(Equivalence::Conjugate(rotations));
+        }
+    }
+
+    Ok(Equivalence::Different)
+}
+
+/// 手順を何回繰り返すと元の状態に戻るかを返す。
+pub fn order(cube: &Cube, movements: &[Movement]) -> Result<usize, CubeError> {
+    Ok(StickerPermutation::new(cube, movements)?.order())
+}
+
+/// Singmaster 記法でのコーナーの面の順序。
+const CORNER_FACES: [[CubeFace; 3]; 8] = [
+    [CubeFace::Up, CubeFace::Front, CubeFace::Right],
+    [CubeFace::Up, CubeFace::Right, CubeFace::Back],
+    [CubeFace::Up, CubeFace::Back, CubeFace::Left],
+    [CubeFace::Up, CubeFace::Left, CubeFace::Front],
+    [CubeFace::Down, CubeFace::Right, CubeFace::Front],
+    [CubeFace::Down, CubeFace::Front, CubeFace::Left],
+    [CubeFace::Down, CubeFace::Left, CubeFace::Back],
+    [CubeFace::Down, CubeFace::Back, CubeFace::Right],
+];
+
+/// ピースの面を向きの基準となる面から順に並べる。
+fn piece_faces(mut faces: Vec<CubeFace>) -> Vec<CubeFace> {
+    if let Some(corner) = CORNER_FACES
+        .iter()
+        .find(|corner| faces.len() == 3 && corner.iter().all(|f| faces.contains(f)))
+    {
+        return corner.to_vec();
+    }
+
+    let priority = |face: &CubeFace| match face {
+        CubeFace::Up => 0,
+        CubeFace::Down => 1,
+        CubeFace::Front => 2,
+        CubeFace::Back => 3,
+        CubeFace::Right => 4,
+        CubeFace::Left => 5,
+    };
+    faces.sort_by_key(priority);
+    faces
+}
+
+/// 面に垂直な軸 (L-R, D-U, B-F の順に 0, 1, 2) を返す。
+fn face_axis(face: CubeFace) -> usize {
+    match face {
+        CubeFace::Left | CubeFace::Right => 0,
+        CubeFace::Up | CubeFace::Down => 1,
+        CubeFace::Front | CubeFace::Back => 2,
+    }
+}
+
+/// 向きの変化を表す記号を返す。
+fn twist_suffix(kind: PieceKind, twist: u8) -> &'static str {
+    match (kind, twist) {
+        (_, 0) | (PieceKind::Center, _) => "",
+        (PieceKind::Corner, 1) => "-",
+        _ => "+",
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn lcm(a: usize, b: usize) -> usize {
+    a / gcd(a, b) * b
+}
@@ -1,8 +1,14 @@
 use crate::notation::{Face as MovementFace, Movement, Rotation as MovementRotation};
-use std::{
-    collections::HashMap,
-    error::Error,
+#[cfg(feature = "std")]
+use crate::{
+    notation::{MovementParseError, Movements},
+    puzzle::Puzzle,
+};
+use alloc::{boxed::Box, collections::BTreeMap, string::String, vec, vec::Vec};
+use core::{
     fmt::{Display, Formatter, Result as FmtResult},
+    hash::{Hash, Hasher},
+    ops::Range,
 };
 
 /// キューブ操作のエラーを表す。
@@ -10,20 +16,51 @@ use std::{
 pub enum CubeError {
     /// 非対応の回転
     UndefinedMovement(Movement),
+
+    /// 範囲外のステッカー位置
+    InvalidPosition {
+        face: CubeFace,
+        row: usize,
+        column: usize,
+    },
+
+    /// 断面が正方形でない層の 90 度回転
+    NonSquareQuarterTurn(Movement),
+
+    /// 隣接していないか範囲外のピース同士の融着
+    InvalidBandage(CubiePosition, CubiePosition),
+
+    /// 融着されたピースを切り離す回転
+    BandageViolation(Movement),
 }
 
 impl Display for CubeError {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         match self {
             CubeError::UndefinedMovement(m) => write!(f, "Undefined movement: {:?}", m),
+            CubeError::InvalidPosition { face, row, column } => write!(
+                f,
+                "Invalid sticker position: {:?} ({}, {})",
+                face, row, column
+            ),
+            CubeError::NonSquareQuarterTurn(m) => {
+                write!(f, "Quarter turn on a non-square layer: {:?}", m)
+            }
+            CubeError::InvalidBandage(a, b) => {
+                write!(f, "Invalid bandage between {:?} and {:?}", a, b)
+            }
+            CubeError::BandageViolation(m) => {
+                write!(f, "Movement cuts through a bandage: {:?}", m)
+            }
         }
     }
 }
 
-impl Error for CubeError {}
+#[cfg(feature = "std")]
+impl std::error::Error for CubeError {}
 
 /// キューブの面を表す。
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum CubeFace {
     /// F 面
     Front,
@@ -44,315 +81,780 @@ pub enum CubeFace {
     Down,
 }
 
+impl CubeFace {
+    /// 全ての面。
+    pub const ALL: [CubeFace; 6] = [
+        CubeFace::Front,
+        CubeFace::Back,
+        CubeFace::Left,
+        CubeFace::Right,
+        CubeFace::Up,
+        CubeFace::Down,
+    ];
+
+    /// 外側から見た時の (法線, 右方向, 下方向) の単位ベクトルを返す。
+    pub(crate) fn frame(self) -> (Vector, Vector, Vector) {
+        match self {
+            CubeFace::Front => ([0, 0, 1], [1, 0, 0], [0, -1, 0]),
+            CubeFace::Back => ([0, 0, -1], [-1, 0, 0], [0, -1, 0]),
+            CubeFace::Left => ([-1, 0, 0], [0, 0, 1], [0, -1, 0]),
+            CubeFace::Right => ([1, 0, 0], [0, 0, -1], [0, -1, 0]),
+            CubeFace::Up => ([0, 1, 0], [1, 0, 0], [0, 0, 1]),
+            CubeFace::Down => ([0, -1, 0], [1, 0, 0], [0, 0, -1]),
+        }
+    }
+
+    /// 法線ベクトルから面を求める。
+    fn from_normal(normal: Vector) -> CubeFace {
+        match normal {
+            [0, 0, 1] => CubeFace::Front,
+            [0, 0, -1] => CubeFace::Back,
+            [-1, 0, 0] => CubeFace::Left,
+            [1, 0, 0] => CubeFace::Right,
+            [0, 1, 0] => CubeFace::Up,
+            [0, -1, 0] => CubeFace::Down,
+            _ => unreachable!("Invalid normal vector"),
+        }
+    }
+}
+
+impl Display for CubeFace {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let letter = match self {
+            CubeFace::Front => 'F',
+            CubeFace::Back => 'B',
+            CubeFace::Left => 'L',
+            CubeFace::Right => 'R',
+            CubeFace::Up => 'U',
+            CubeFace::Down => 'D',
+        };
+        write!(f, "{}", letter)
+    }
+}
+
+/// ピース (キュービー) の位置を表す。
+/// (L-R, D-U, B-F) 方向の 0 から始まるインデックスで、L, D, B 面側が 0 となる。
+pub type CubiePosition = (usize, usize, usize);
+
+/// 1 枚のステッカーの状態を表す。
+/// キューブ以外のパズルでは `F` にそのパズルの面を指定する。
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Sticker<F = CubeFace> {
+    /// 特定の面の色
+    Face(F),
+
+    /// 不明な色 (灰色で描画される)
+    Unknown,
+
+    /// 任意の色 (SVG の色指定としてそのまま出力される)
+    Custom(String),
+}
+
+impl<F> From<F> for Sticker<F> {
+    fn from(face: F) -> Sticker<F> {
+        Sticker::Face(face)
+    }
+}
+
+/// ステッカーの向きを記録する範囲を表す。
+#[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum OrientationTracking {
+    /// 記録しない
+    #[default]
+    Disabled,
+
+    /// センターピースのみ (スーパーキューブ)
+    Centers,
+
+    /// 全てのステッカー
+    All,
+}
+
+/// 整数の 3 次元ベクトル。
+/// キューブの中心を原点とし、ピース 1 個分の長さを 2 とする。
+type Vector = [isize; 3];
+
+/// 回転軸を表す。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    /// R 面方向
+    X,
+
+    /// U 面方向
+    Y,
+
+    /// F 面方向
+    Z,
+}
+
+impl Axis {
+    /// ベクトルの成分のインデックス
+    fn index(self) -> usize {
+        match self {
+            Axis::X => 0,
+            Axis::Y => 1,
+            Axis::Z => 2,
+        }
+    }
+
+    /// 単位ベクトルが沿う軸のインデックスを求める。
+    fn index_of(vector: Vector) -> usize {
+        vector
+            .iter()
+            .position(|&v| v != 0)
+            .expect("Unit vector should not be zero")
+    }
+
+    /// 正の方向から見て時計回りに 90 度回転する。
+    fn turn(self, [x, y, z]: Vector) -> Vector {
+        match self {
+            Axis::X => [x, z, -y],
+            Axis::Y => [-z, y, x],
+            Axis::Z => [y, -x, z],
+        }
+    }
+}
+
 /// 仮想的なキューブを表す。
+/// 各辺の分割数が異なる直方体 (2x2x3 など) も扱える。
+///
+/// `PartialEq` と `Hash` はステッカー、記録対象の向き、融着の状態を厳密に比較する。
+/// 全体回転を同一視する場合は `canonical` の結果を比較する。
+#[derive(Debug, Clone)]
 pub struct Cube {
-    /// 辺ごとの分割数
-    divisions: usize,
+    /// X (L-R), Y (D-U), Z (B-F) 各軸の分割数
+    dimensions: [usize; 3],
 
-    /// 面回転時のインデックス対応
-    face_transform: Box<[usize]>,
+    /// 向きを記録する範囲
+    tracking: OrientationTracking,
 
     /// 現在の面の状態
-    faces: HashMap<CubeFace, Box<[CubeFace]>>,
+    faces: BTreeMap<CubeFace, Box<[Sticker]>>,
+
+    /// 各ステッカーの向き (時計回りに 90 度単位)
+    orientations: BTreeMap<CubeFace, Box<[u8]>>,
+
+    /// 融着されたピースの中心座標の組
+    bandages: Vec<(Vector, Vector)>,
 }
 
 impl Cube {
     pub fn new(divisions: usize) -> Cube {
-        let mut face_transform_source = Vec::with_capacity(divisions * divisions);
-        for i in 0..(divisions * divisions) {
-            let (x1, y1) = (i % divisions, i / divisions);
-            let (x2, y2) = (y1, divisions - x1 - 1);
-            let i_90 = y2 * divisions + x2;
-            face_transform_source.push(i_90);
-        }
+        Cube::cuboid(divisions, divisions, divisions)
+    }
 
-        let make_face = |f| {
-            (0..(divisions.pow(2)))
-                .map(|_| f)
-                .collect::<Vec<_>>()
-                .into_boxed_slice()
-        };
-        let mut faces = HashMap::with_capacity(6);
-        faces.insert(CubeFace::Front, make_face(CubeFace::Front));
-        faces.insert(CubeFace::Back, make_face(CubeFace::Back));
-        faces.insert(CubeFace::Left, make_face(CubeFace::Left));
-        faces.insert(CubeFace::Right, make_face(CubeFace::Right));
-        faces.insert(CubeFace::Up, make_face(CubeFace::Up));
-        faces.insert(CubeFace::Down, make_face(CubeFace::Down));
+    /// 直方体のパズルを生成する。
+    /// 例えば 2x2x3 (高さ 3) は `Cube::cuboid(2, 3, 2)` となる。
+    ///
+    /// * `width`: L-R 方向の分割数
+    /// * `height`: D-U 方向の分割数
+    /// * `depth`: B-F 方向の分割数
+    pub fn cuboid(width: usize, height: usize, depth: usize) -> Cube {
+        let dimensions = [width, height, depth];
+        let mut faces = BTreeMap::new();
+        let mut orientations = BTreeMap::new();
+        for &face in &CubeFace::ALL {
+            let (rows, columns) = face_size(dimensions, face);
+            faces.insert(
+                face,
+                vec![Sticker::Face(face); rows * columns].into_boxed_slice(),
+            );
+            orientations.insert(face, vec![0; rows * columns].into_boxed_slice());
+        }
 
         Cube {
-            face_transform: face_transform_source.into_boxed_slice(),
+            dimensions,
+            tracking: OrientationTracking::Disabled,
             faces,
-            divisions,
+            orientations,
+            bandages: vec![],
         }
     }
 
+    /// 辺ごとの分割数を返す。
+    /// 直方体の場合は幅 (L-R 方向) を返すため、`dimensions` か `face_size` を使うこと。
     pub fn divisions(&self) -> usize {
-        self.divisions
+        self.dimensions[0]
+    }
+
+    /// (幅, 高さ, 奥行き) の分割数を返す。
+    pub fn dimensions(&self) -> (usize, usize, usize) {
+        let [width, height, depth] = self.dimensions;
+        (width, height, depth)
     }
 
-    pub fn faces(&self) -> &HashMap<CubeFace, Box<[CubeFace]>> {
+    /// 全ての辺の分割数が等しいかどうかを返す。
+    pub fn is_cube(&self) -> bool {
+        let [width, height, depth] = self.dimensions;
+        width == height && height == depth
+    }
+
+    /// 面の (行数, 列数) を返す。
+    pub fn face_size(&self, face: CubeFace) -> (usize, usize) {
+        face_size(self.dimensions, face)
+    }
+
+    pub fn faces(&self) -> &BTreeMap<CubeFace, Box<[Sticker]>> {
         &self.faces
     }
 
+    /// 指定した位置のステッカーを返す。
+    pub fn sticker(&self, face: CubeFace, row: usize, column: usize) -> Option<&Sticker> {
+        let index = self.sticker_index(face, row, column)?;
+        self.faces.get(&face).map(|stickers| &stickers[index])
+    }
+
+    /// 指定した位置のステッカーを塗り替える。
+    /// 回転操作では到達できない状態や、一部が不明な状態を表現するために使う。
+    pub fn set_sticker(
+        &mut self,
+        face: CubeFace,
+        row: usize,
+        column: usize,
+        sticker: impl Into<Sticker>,
+    ) -> Result<(), CubeError> {
+        let index = self
+            .sticker_index(face, row, column)
+            .ok_or(CubeError::InvalidPosition { face, row, column })?;
+        let stickers = self
+            .faces
+            .get_mut(&face)
+            .expect("Existing faces should be registered");
+        stickers[index] = sticker.into();
+        Ok(())
+    }
+
+    /// 面全体を同じステッカーで塗り替える。
+    pub fn fill_face(&mut self, face: CubeFace, sticker: impl Into<Sticker>) {
+        let sticker = sticker.into();
+        let stickers = self
+            .faces
+            .get_mut(&face)
+            .expect("Existing faces should be registered");
+        for s in stickers.iter_mut() {
+            *s = sticker.clone();
+        }
+    }
+
+    /// 向きを記録する範囲を返す。
+    pub fn orientation_tracking(&self) -> OrientationTracking {
+        self.tracking
+    }
+
+    /// 向きを記録する範囲を設定する。
+    /// 向き自体は常に更新されているため、途中で変更しても整合性は保たれる。
+    pub fn set_orientation_tracking(&mut self, tracking: OrientationTracking) {
+        self.tracking = tracking;
+    }
+
+    /// 指定した位置のステッカーの向きを返す。
+    /// 記録対象でないステッカーについては `None` を返す。
+    ///
+    /// 向きは面の上方向を 0 とし、外側から見て時計回りに 90 度単位で数える。
+    pub fn orientation(&self, face: CubeFace, row: usize, column: usize) -> Option<u8> {
+        let index = self.sticker_index(face, row, column)?;
+        if !self.is_tracked(face, row, column) {
+            return None;
+        }
+        self.orientations
+            .get(&face)
+            .map(|orientations| orientations[index])
+    }
+
+    /// 指定した位置のステッカーの向きを設定する。
+    pub fn set_orientation(
+        &mut self,
+        face: CubeFace,
+        row: usize,
+        column: usize,
+        orientation: u8,
+    ) -> Result<(), CubeError> {
+        let index = self
+            .sticker_index(face, row, column)
+            .ok_or(CubeError::InvalidPosition { face, row, column })?;
+        let orientations = self
+            .orientations
+            .get_mut(&face)
+            .expect("Existing faces should be registered");
+        orientations[index] = orientation % 4;
+        Ok(())
+    }
+
+    /// 隣接する 2 つのピースを融着する。
+    /// 融着されたピースは常に一緒に動き、それらを切り離す回転は適用できなくなる。
+    pub fn add_bandage(&mut self, a: CubiePosition, b: CubiePosition) -> Result<(), CubeError> {
+        let (center_a, center_b) = match (self.cubie_center(a), self.cubie_center(b)) {
+            (Some(center_a), Some(center_b)) => (center_a, center_b),
+            _ => return Err(CubeError::InvalidBandage(a, b)),
+        };
+        let distance: isize = center_a
+            .iter()
+            .zip(center_b.iter())
+            .map(|(l, r)| (l - r).abs())
+            .sum();
+        if distance != 2 {
+            return Err(CubeError::InvalidBandage(a, b));
+        }
+
+        self.bandages.push((center_a, center_b));
+        Ok(())
+    }
+
+    /// 融着されている全てのピースの組を現在の位置で返す。
+    pub fn bandages(&self) -> Vec<(CubiePosition, CubiePosition)> {
+        self.bandages
+            .iter()
+            .map(|&(a, b)| (self.cubie_position(a), self.cubie_position(b)))
+            .collect()
+    }
+
+    /// ピースがいずれかのピースと融着されているかどうかを返す。
+    pub fn is_bandaged(&self, cubie: CubiePosition) -> bool {
+        match self.cubie_center(cubie) {
+            Some(center) => self
+                .bandages
+                .iter()
+                .any(|&(a, b)| a == center || b == center),
+            None => false,
+        }
+    }
+
+    /// 2 つのピースが (他のピースを介してでも) 融着されて 1 つのブロックになっているかどうかを返す。
+    pub fn is_fused(&self, a: CubiePosition, b: CubiePosition) -> bool {
+        let (start, goal) = match (self.cubie_center(a), self.cubie_center(b)) {
+            (Some(start), Some(goal)) => (start, goal),
+            _ => return false,
+        };
+
+        let mut visited = vec![start];
+        let mut pending = vec![start];
+        while let Some(current) = pending.pop() {
+            if current == goal {
+                return true;
+            }
+            for &(l, r) in &self.bandages {
+                let next = match current {
+                    c if c == l => r,
+                    c if c == r => l,
+                    _ => continue,
+                };
+                if !visited.contains(&next) {
+                    visited.push(next);
+                    pending.push(next);
+                }
+            }
+        }
+        false
+    }
+
+    /// ステッカーが貼られているピースの位置を返す。
+    pub fn sticker_cubie(
+        &self,
+        face: CubeFace,
+        row: usize,
+        column: usize,
+    ) -> Option<CubiePosition> {
+        let index = self.sticker_index(face, row, column)?;
+        let (normal, _, _) = face.frame();
+        let position = self.position(face, index);
+        let mut center = [0; 3];
+        for (i, c) in center.iter_mut().enumerate() {
+            *c = position[i] - normal[i];
+        }
+        Some(self.cubie_position(center))
+    }
+
+    /// 全体回転で得られる全ての状態を、適用した回転記号列と組にして返す。
+    /// 立方体では 24 通りで、直方体では形が変わらない回転のみが含まれる。
+    pub fn rotated_variants(&self) -> Vec<(Vec<Movement>, Cube)> {
+        let rotation = |target, direction| Movement { target, direction };
+        let tilts = [
+            vec![],
+            vec![rotation(MovementFace::X, MovementRotation::Clockwise)],
+            vec![rotation(MovementFace::X, MovementRotation::Turnover)],
+            vec![rotation(
+                MovementFace::X,
+                MovementRotation::Counterclockwise,
+            )],
+            vec![rotation(MovementFace::Z, MovementRotation::Clockwise)],
+            vec![rotation(
+                MovementFace::Z,
+                MovementRotation::Counterclockwise,
+            )],
+        ];
+        let turns = [
+            None,
+            Some(MovementRotation::Clockwise),
+            Some(MovementRotation::Turnover),
+            Some(MovementRotation::Counterclockwise),
+        ];
+
+        let mut variants = Vec::with_capacity(24);
+        for tilt in &tilts {
+            for turn in &turns {
+                let mut movements = tilt.clone();
+                movements.extend(turn.map(|d| rotation(MovementFace::Y, d)));
+
+                let mut cube = self.clone();
+                if movements.iter().all(|&m| cube.apply(m).is_ok()) {
+                    variants.push((movements, cube));
+                }
+            }
+        }
+        variants
+    }
+
+    /// 全体回転を同一視した時の代表となる状態を返す。
+    /// 全体回転でのみ異なる状態は同じ結果になるため、重複の除去に使える。
+    pub fn canonical(&self) -> Cube {
+        self.rotated_variants()
+            .into_iter()
+            .map(|(_, cube)| cube)
+            .min_by(|a, b| a.state_key().cmp(&b.state_key()))
+            .expect("Identity rotation should always be included")
+    }
+
+    /// 全体回転を同一視した時に等しいかどうかを返す。
+    pub fn eq_modulo_rotation(&self, other: &Cube) -> bool {
+        self.rotation_to(other).is_some()
+    }
+
+    /// この状態に適用すると `other` と等しくなる全体回転を返す。
+    pub fn rotation_to(&self, other: &Cube) -> Option<Vec<Movement>> {
+        self.rotated_variants()
+            .into_iter()
+            .find(|(_, cube)| cube == other)
+            .map(|(movements, _)| movements)
+    }
+
+    /// 揃っているかどうかを返す。
+    /// 向きを記録している場合は、全体回転で全てのステッカーの向きが 0 になる必要がある。
+    pub fn is_solved(&self) -> bool {
+        let uniform = self
+            .faces
+            .values()
+            .all(|stickers| stickers.iter().all(|s| Some(s) == stickers.first()));
+        if !uniform {
+            return false;
+        }
+        if self.tracking == OrientationTracking::Disabled {
+            return true;
+        }
+
+        let is_aligned = |cube: &Cube| {
+            CubeFace::ALL.iter().all(|&face| {
+                let (rows, columns) = cube.face_size(face);
+                (0..rows).all(|row| {
+                    (0..columns).all(|column| cube.orientation(face, row, column).unwrap_or(0) == 0)
+                })
+            })
+        };
+        self.rotated_variants()
+            .iter()
+            .any(|(_, cube)| is_aligned(cube))
+    }
+
     /// 回転操作を適用する。
+    /// 直方体で断面が正方形でない層は 180 度回転のみ可能。
     pub fn apply(&mut self, movement: Movement) -> Result<(), CubeError> {
         let count = match movement.direction {
             MovementRotation::Clockwise => 1,
             MovementRotation::Turnover => 2,
             MovementRotation::Counterclockwise => 3,
         };
+        let [width, height, depth] = self.dimensions;
 
-        match movement.target {
+        let (axis, layers, count) = match movement.target {
             // 通常回転
-            MovementFace::Front(l) => {
-                self.turn_face(CubeFace::Front, count);
-                for i in 0..l {
-                    self.turn_layer_z(self.divisions - 1 - i, count);
-                }
-            }
-            MovementFace::Back(l) => {
-                self.turn_face(CubeFace::Back, count);
-                for i in 0..l {
-                    self.turn_layer_z(i, 4 - count);
-                }
-            }
-            MovementFace::Left(l) => {
-                self.turn_face(CubeFace::Left, count);
-                for i in 0..l {
-                    self.turn_layer_x(i, 4 - count);
-                }
-            }
-            MovementFace::Right(l) => {
-                self.turn_face(CubeFace::Right, count);
-                for i in 0..l {
-                    self.turn_layer_x(self.divisions - 1 - i, count);
-                }
-            }
-            MovementFace::Up(l) => {
-                self.turn_face(CubeFace::Up, count);
-                for i in 0..l {
-                    self.turn_layer_y(i, count);
-                }
-            }
-            MovementFace::Down(l) => {
-                self.turn_face(CubeFace::Down, count);
-                for i in 0..l {
-                    self.turn_layer_y(self.divisions - 1 - i, 4 - count);
-                }
-            }
+            MovementFace::Front(l) if l > 0 && l <= depth => (Axis::Z, (depth - l)..depth, count),
+            MovementFace::Back(l) if l > 0 && l <= depth => (Axis::Z, 0..l, 4 - count),
+            MovementFace::Left(l) if l > 0 && l <= width => (Axis::X, 0..l, 4 - count),
+            MovementFace::Right(l) if l > 0 && l <= width => (Axis::X, (width - l)..width, count),
+            MovementFace::Up(l) if l > 0 && l <= height => (Axis::Y, (height - l)..height, count),
+            MovementFace::Down(l) if l > 0 && l <= height => (Axis::Y, 0..l, 4 - count),
 
             // 中層回転
-            // TODO これでいいの？
-            MovementFace::Standing => {
-                if self.divisions != 3 {
-                    return Err(CubeError::UndefinedMovement(movement));
-                }
-                self.turn_layer_z(1, count);
-            }
-            MovementFace::Middle => {
-                if self.divisions != 3 {
-                    return Err(CubeError::UndefinedMovement(movement));
-                }
-                self.turn_layer_x(1, 4 - count);
-            }
-            MovementFace::Equational => {
-                if self.divisions != 3 {
-                    return Err(CubeError::UndefinedMovement(movement));
-                }
-                self.turn_layer_y(1, count);
-            }
+            // S は F 面、M は L 面、E は D 面と同じ方向に回転する
+            MovementFace::Standing if depth == 3 => (Axis::Z, 1..2, count),
+            MovementFace::Middle if width == 3 => (Axis::X, 1..2, 4 - count),
+            MovementFace::Equational if height == 3 => (Axis::Y, 1..2, 4 - count),
 
             // 全体回転
-            MovementFace::X => {
-                self.turn_face(CubeFace::Right, count);
-                self.turn_face(CubeFace::Left, 4 - count);
-                for i in 0..(self.divisions) {
-                    self.turn_layer_x(i, count);
-                }
-            }
-            MovementFace::Y => {
-                self.turn_face(CubeFace::Up, count);
-                self.turn_face(CubeFace::Down, 4 - count);
-                for i in 0..(self.divisions) {
-                    self.turn_layer_y(i, count);
-                }
-            }
-            MovementFace::Z => {
-                self.turn_face(CubeFace::Front, count);
-                self.turn_face(CubeFace::Back, 4 - count);
-                for i in 0..(self.divisions) {
-                    self.turn_layer_z(i, count);
-                }
-            }
+            MovementFace::X => (Axis::X, 0..width, count),
+            MovementFace::Y => (Axis::Y, 0..height, count),
+            MovementFace::Z => (Axis::Z, 0..depth, count),
+
+            _ => return Err(CubeError::UndefinedMovement(movement)),
+        };
+
+        if count % 2 == 1 && !self.is_square_section(axis) {
+            return Err(CubeError::NonSquareQuarterTurn(movement));
         }
+        let cuts_bandage = self.bandages.iter().any(|&(a, b)| {
+            layers.contains(&self.layer(axis, a)) != layers.contains(&self.layer(axis, b))
+        });
+        if cuts_bandage {
+            return Err(CubeError::BandageViolation(movement));
+        }
+        self.turn_layers(axis, layers, count);
         Ok(())
     }
 
-    /// 面のみ回転する。
-    ///
-    /// * `face`: 対象の面
-    /// * `count`: 時計回りに 90 度単位で回転する回数。
-    fn turn_face(&mut self, face: CubeFace, count: usize) {
-        let mut modified = self
-            .faces
-            .get(&face)
-            .expect("Existing faces should be registered")
-            .clone();
-        for _ in 0..count {
-            let next = self
-                .face_transform
-                .iter()
-                .map(|&tr| modified[tr])
-                .collect::<Vec<_>>()
-                .into_boxed_slice();
-            modified = next;
+    /// 比較とハッシュに用いる状態を生成する。
+    fn state_key(&self) -> StateKey<'_> {
+        let mut stickers = vec![];
+        let mut orientations = vec![];
+        for &face in &CubeFace::ALL {
+            let (rows, columns) = self.face_size(face);
+            stickers.extend(self.faces[&face].iter());
+            for i in 0..(rows * columns) {
+                orientations.push(self.orientation(face, i / columns, i % columns));
+            }
         }
 
-        self.faces.insert(face, modified);
+        let mut bandages: Vec<_> = self
+            .bandages()
+            .into_iter()
+            .map(|(a, b)| (a.min(b), a.max(b)))
+            .collect();
+        bandages.sort();
+
+        StateKey {
+            dimensions: self.dimensions,
+            stickers,
+            orientations,
+            bandages,
+        }
     }
 
-    /// 層のみを X 軸にそって回転する。
-    /// 回転方向は R 面時計回り。
-    ///
-    /// * `column_front`: F 面における列位置
-    /// * `count`: 回転回数
-    fn turn_layer_x(&mut self, column_front: usize, count: usize) {
-        for _ in 0..count {
-            let front_column = self.extract_column(CubeFace::Front, column_front);
-            let up_column = self.swap_to_column(CubeFace::Up, column_front, &front_column, false);
-            let back_column = self.swap_to_column(
-                CubeFace::Back,
-                self.divisions - 1 - column_front,
-                &up_column,
-                true,
-            );
-            let down_column = self.swap_to_column(CubeFace::Down, column_front, &back_column, true);
-            self.swap_to_column(CubeFace::Front, column_front, &down_column, false);
+    /// 面の位置からインデックスを求める。
+    fn sticker_index(&self, face: CubeFace, row: usize, column: usize) -> Option<usize> {
+        let (rows, columns) = self.face_size(face);
+        if row >= rows || column >= columns {
+            return None;
         }
+        Some(row * columns + column)
     }
 
-    /// 層のみを Y 軸にそって回転する。
-    /// 回転方向は U 面時計回り。
-    ///
-    /// * `row_right`: R 面における行位置
-    /// * `count`: 回転回数
-    fn turn_layer_y(&mut self, row_right: usize, count: usize) {
-        for _ in 0..count {
-            let right_row = self.extract_row(CubeFace::Right, row_right);
-            let front_row = self.swap_to_row(CubeFace::Front, row_right, &right_row, false);
-            let left_row = self.swap_to_row(CubeFace::Left, row_right, &front_row, false);
-            let back_row = self.swap_to_row(CubeFace::Back, row_right, &left_row, false);
-            self.swap_to_row(CubeFace::Right, row_right, &back_row, false);
+    /// 軸に垂直な断面が正方形かどうかを返す。
+    fn is_square_section(&self, axis: Axis) -> bool {
+        let [width, height, depth] = self.dimensions;
+        match axis {
+            Axis::X => height == depth,
+            Axis::Y => width == depth,
+            Axis::Z => width == height,
         }
     }
 
-    /// 層のみを Z 軸にそって回転する。
-    /// 回転方向は F 面時計回り。
-    ///
-    /// * `column_front`: U 面における行位置
-    /// * `count`: 回転回数
-    fn turn_layer_z(&mut self, row_up: usize, count: usize) {
-        for _ in 0..count {
-            let up_row = self.extract_row(CubeFace::Up, row_up);
-            let right_column =
-                self.swap_to_column(CubeFace::Right, self.divisions - 1 - row_up, &up_row, false);
-            let down_row = self.swap_to_row(
-                CubeFace::Down,
-                self.divisions - 1 - row_up,
-                &right_column,
-                true,
-            );
-            let left_row = self.swap_to_column(CubeFace::Left, row_up, &down_row, false);
-            self.swap_to_row(CubeFace::Up, row_up, &left_row, true);
+    /// 向きを記録する対象のステッカーかどうかを返す。
+    fn is_tracked(&self, face: CubeFace, row: usize, column: usize) -> bool {
+        let (rows, columns) = self.face_size(face);
+        match self.tracking {
+            OrientationTracking::Disabled => false,
+            OrientationTracking::Centers => {
+                0 < row && row < rows - 1 && 0 < column && column < columns - 1
+            }
+            OrientationTracking::All => true,
         }
     }
 
-    /// 行を抽出する。
-    fn extract_row(&self, face: CubeFace, row: usize) -> Box<[CubeFace]> {
-        self.faces
-            .get(&face)
-            .expect("Existing faces should be registered")
-            .iter()
-            .skip(row * self.divisions)
-            .take(self.divisions)
-            .copied()
-            .collect::<Vec<_>>()
-            .into_boxed_slice()
-    }
+    /// 層を回転する。
+    /// ステッカーの位置と向きを 3 次元的に回転させ、移動先の面とインデックスを求める。
+    ///
+    /// * `axis`: 回転軸
+    /// * `layers`: 回転する層の範囲 (軸の負の方向から数える)
+    /// * `count`: 軸の正の方向から見て時計回りに 90 度単位で回転する回数
+    fn turn_layers(&mut self, axis: Axis, layers: Range<usize>, count: usize) {
+        let mut faces = self.faces.clone();
+        let mut orientations = self.orientations.clone();
+
+        for &face in &CubeFace::ALL {
+            for (i, sticker) in self.faces[&face].iter().enumerate() {
+                let mut position = self.position(face, i);
+                if !layers.contains(&self.layer(axis, position)) {
+                    continue;
+                }
 
-    /// 列を抽出する。
-    fn extract_column(&self, face: CubeFace, column: usize) -> Box<[CubeFace]> {
-        let divisions = self.divisions;
-        self.faces
-            .get(&face)
-            .expect("Existing faces should be registered")
-            .iter()
-            .enumerate()
-            .filter_map(|(i, &v)| {
-                if i % divisions == column {
-                    Some(v)
-                } else {
-                    None
+                let (mut normal, _, _) = face.frame();
+                let mut up = orientation_vector(face, self.orientations[&face][i]);
+                for _ in 0..count {
+                    position = axis.turn(position);
+                    normal = axis.turn(normal);
+                    up = axis.turn(up);
                 }
-            })
-            .collect::<Vec<_>>()
-            .into_boxed_slice()
-    }
 
-    /// 特定の面の行と入れ替える。
-    fn swap_to_row(
-        &mut self,
-        target_face: CubeFace,
-        target_row: usize,
-        values: &[CubeFace],
-        invert: bool,
-    ) -> Box<[CubeFace]> {
-        assert_eq!(values.len(), self.divisions);
-        let popped = self.extract_row(target_face, target_row);
-        let face = self
-            .faces
-            .get_mut(&target_face)
-            .expect("Existing faces should be registered");
+                let target_face = CubeFace::from_normal(normal);
+                let target_index = self.index(target_face, position);
+                faces
+                    .get_mut(&target_face)
+                    .expect("Existing faces should be registered")[target_index] = sticker.clone();
+                orientations
+                    .get_mut(&target_face)
+                    .expect("Existing faces should be registered")[target_index] =
+                    orientation_of(target_face, up);
+            }
+        }
 
-        if invert {
-            for (i, &v) in values.iter().rev().enumerate() {
-                face[target_row * self.divisions + i] = v;
+        // 融着を切る回転は事前に弾いているため、片方が層に含まれれば両方とも回転する
+        for i in 0..self.bandages.len() {
+            let (mut a, mut b) = self.bandages[i];
+            if !layers.contains(&self.layer(axis, a)) {
+                continue;
             }
-        } else {
-            for (i, &v) in values.iter().enumerate() {
-                face[target_row * self.divisions + i] = v;
+            for _ in 0..count {
+                a = axis.turn(a);
+                b = axis.turn(b);
             }
+            self.bandages[i] = (a, b);
         }
 
-        popped
+        self.faces = faces;
+        self.orientations = orientations;
     }
 
-    /// 特定の面の列と入れ替える。
-    fn swap_to_column(
-        &mut self,
-        target_face: CubeFace,
-        target_column: usize,
-        values: &[CubeFace],
-        invert: bool,
-    ) -> Box<[CubeFace]> {
-        assert_eq!(values.len(), self.divisions);
-        let popped = self.extract_column(target_face, target_column);
-        let face = self
-            .faces
-            .get_mut(&target_face)
-            .expect("Existing faces should be registered");
+    /// ステッカーの中心座標を求める。
+    fn position(&self, face: CubeFace, index: usize) -> Vector {
+        let (normal, right, down) = face.frame();
+        let (_, columns) = self.face_size(face);
+        let (row, column) = ((index / columns) as isize, (index % columns) as isize);
+        let depth = self.dimensions[Axis::index_of(normal)] as isize;
+        let columns = self.dimensions[Axis::index_of(right)] as isize;
+        let rows = self.dimensions[Axis::index_of(down)] as isize;
+
+        let mut position = [0; 3];
+        for (i, p) in position.iter_mut().enumerate() {
+            *p = normal[i] * depth
+                + right[i] * (column * 2 - columns + 1)
+                + down[i] * (row * 2 - rows + 1);
+        }
+        position
+    }
 
-        if invert {
-            for (i, &v) in values.iter().rev().enumerate() {
-                face[target_column + self.divisions * i] = v;
-            }
-        } else {
-            for (i, &v) in values.iter().enumerate() {
-                face[target_column + self.divisions * i] = v;
+    /// ステッカーの中心座標から面上のインデックスを求める。
+    fn index(&self, face: CubeFace, position: Vector) -> usize {
+        let (_, right, down) = face.frame();
+        let columns = self.dimensions[Axis::index_of(right)] as isize;
+        let rows = self.dimensions[Axis::index_of(down)] as isize;
+        let column = (dot(position, right) + columns - 1) / 2;
+        let row = (dot(position, down) + rows - 1) / 2;
+        (row * columns + column) as usize
+    }
+
+    /// ピースの位置から中心座標を求める。
+    fn cubie_center(&self, (x, y, z): CubiePosition) -> Option<Vector> {
+        let indices = [x, y, z];
+        let mut center = [0; 3];
+        for (i, c) in center.iter_mut().enumerate() {
+            if indices[i] >= self.dimensions[i] {
+                return None;
             }
+            *c = indices[i] as isize * 2 - self.dimensions[i] as isize + 1;
         }
+        Some(center)
+    }
+
+    /// ピースの中心座標から位置を求める。
+    fn cubie_position(&self, center: Vector) -> CubiePosition {
+        let index = |i: usize| ((center[i] + self.dimensions[i] as isize - 1) / 2) as usize;
+        (index(0), index(1), index(2))
+    }
 
-        popped
+    /// ステッカーまたはピースの中心座標が属する層を求める。
+    fn layer(&self, axis: Axis, position: Vector) -> usize {
+        let size = self.dimensions[axis.index()] as isize;
+        let layer = (position[axis.index()] + size - 1).max(0) / 2;
+        layer as usize
     }
 }
+
+#[cfg(feature = "std")]
+impl Puzzle for Cube {
+    type Movement = Movement;
+    type Face = CubeFace;
+    type ParseError = MovementParseError;
+    type Error = CubeError;
+
+    const FACES: &'static [CubeFace] = &CubeFace::ALL;
+
+    fn parse_movements(source: &str) -> Result<Vec<Movement>, MovementParseError> {
+        Movements::new(source).collect()
+    }
+
+    fn inverse_movement(movement: Movement) -> Movement {
+        movement.inverse()
+    }
+
+    fn apply_movement(&mut self, movement: Movement) -> Result<(), CubeError> {
+        self.apply(movement)
+    }
+
+    fn stickers(&self, face: CubeFace) -> &[Sticker] {
+        &self.faces[&face]
+    }
+
+    fn is_solved(&self) -> bool {
+        Cube::is_solved(self)
+    }
+}
+
+impl PartialEq for Cube {
+    fn eq(&self, other: &Cube) -> bool {
+        self.state_key() == other.state_key()
+    }
+}
+
+impl Eq for Cube {}
+
+impl Hash for Cube {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.state_key().hash(state);
+    }
+}
+
+/// `Cube` の比較とハッシュに用いる状態。
+/// 面は `CubeFace::ALL` の順に並べ、融着は位置の順に整列する。
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct StateKey<'a> {
+    dimensions: [usize; 3],
+    stickers: Vec<&'a Sticker>,
+    orientations: Vec<Option<u8>>,
+    bandages: Vec<(CubiePosition, CubiePosition)>,
+}
+
+/// 各軸の分割数から面の (行数, 列数) を求める。
+fn face_size(dimensions: [usize; 3], face: CubeFace) -> (usize, usize) {
+    let (_, right, down) = face.frame();
+    (
+        dimensions[Axis::index_of(down)],
+        dimensions[Axis::index_of(right)],
+    )
+}
+
+/// 内積を求める。
+fn dot(lhs: Vector, rhs: Vector) -> isize {
+    lhs.iter().zip(rhs.iter()).map(|(l, r)| l * r).sum()
+}
+
+/// ステッカーの向きをベクトルに変換する。
+fn orientation_vector(face: CubeFace, orientation: u8) -> Vector {
+    let (_, right, down) = face.frame();
+    match orientation {
+        0 => [-down[0], -down[1], -down[2]],
+        1 => right,
+        2 => down,
+        3 => [-right[0], -right[1], -right[2]],
+        _ => unreachable!("Orientation should be in 0..4"),
+    }
+}
+
+/// ベクトルをステッカーの向きに変換する。
+fn orientation_of(face: CubeFace, vector: Vector) -> u8 {
+    (0..4)
+        .find(|&o| orientation_vector(face, o) == vector)
+        .expect("Orientation vector should lie on the face")
+}
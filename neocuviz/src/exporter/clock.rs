@@ -0,0 +1,130 @@
+use super::{
+    svg::{SvgElement, SvgEmitter},
+    Exporter, ExporterParameters,
+};
+use crate::puzzle::{Clock, ClockFace};
+
+use std::{
+    f64::consts::PI,
+    io::{prelude::*, Result as IoResult},
+};
+
+/// 円を近似する多角形の頂点数。
+const CIRCLE_SEGMENTS: usize = 36;
+
+/// ダイヤルの間隔。
+const DIAL_SPACING: f64 = 0.26;
+
+/// ダイヤルの半径。
+const DIAL_RADIUS: f64 = 0.095;
+
+/// クロックの両面のダイヤルとピンを左右に並べて表示する `Exporter`。
+/// 左に `ClockFace::Front`、右に `ClockFace::Back` をそれぞれ外側から見た状態で描画する。
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ClockFaces {
+    params: ExporterParameters<ClockFace>,
+}
+
+impl Exporter<Clock> for ClockFaces {
+    fn set_params(&mut self, params: &ExporterParameters<ClockFace>) {
+        self.params = params.clone();
+    }
+
+    fn write(&self, clock: &Clock, writer: &mut dyn Write) -> IoResult<()> {
+        let mut emitter = SvgEmitter::new(self.params.size, self.params.size);
+        self.draw_face(&mut emitter, clock, ClockFace::Front, (-0.5, 0.0));
+        self.draw_face(&mut emitter, clock, ClockFace::Back, (0.5, 0.0));
+        emitter.emit(writer)
+    }
+}
+
+impl ClockFaces {
+    /// 1 つの面を描画する。
+    fn draw_face(
+        &self,
+        emitter: &mut SvgEmitter,
+        clock: &Clock,
+        face: ClockFace,
+        center: (f64, f64),
+    ) {
+        emitter.add_element(SvgElement::StrokeFillPolygon {
+            stroke_color: "#000".into(),
+            fill_color: self.params.colors[&face].clone(),
+            thickness: 0.02,
+            points: circle(center, 0.46),
+        });
+
+        for (i, &hour) in clock.dials(face).iter().enumerate() {
+            let dial = (
+                center.0 + (i % 3) as f64 * DIAL_SPACING - DIAL_SPACING,
+                center.1 - (i / 3) as f64 * DIAL_SPACING + DIAL_SPACING,
+            );
+            self.draw_dial(emitter, dial, hour);
+        }
+
+        let pins = clock.pins(face).to_array();
+        for (i, &up) in pins.iter().enumerate() {
+            let x = if i % 2 == 0 { -0.5 } else { 0.5 };
+            let y = if i < 2 { 0.5 } else { -0.5 };
+            let pin = (center.0 + x * DIAL_SPACING, center.1 + y * DIAL_SPACING);
+            emitter.add_element(SvgElement::StrokeFillPolygon {
+                stroke_color: "#000".into(),
+                fill_color: if up { "#ff0" } else { "#444" }.into(),
+                thickness: 0.01,
+                points: circle(pin, 0.035),
+            });
+        }
+    }
+
+    /// 1 つのダイヤルを描画する。
+    fn draw_dial(&self, emitter: &mut SvgEmitter, center: (f64, f64), hour: u8) {
+        emitter.add_element(SvgElement::StrokeFillPolygon {
+            stroke_color: "#000".into(),
+            fill_color: "#fff".into(),
+            thickness: 0.01,
+            points: circle(center, DIAL_RADIUS),
+        });
+
+        // 12 時の目盛り
+        emitter.add_element(SvgElement::Line {
+            color: "#f00".into(),
+            thickness: 0.012,
+            start: (center.0, center.1 + DIAL_RADIUS * 0.75),
+            end: (center.0, center.1 + DIAL_RADIUS),
+        });
+
+        let angle = hour as f64 * PI / 6.0;
+        let (sin, cos) = angle.sin_cos();
+        let direction = (sin, cos);
+        let normal = (cos, -sin);
+        let width = DIAL_RADIUS * 0.25;
+        let length = DIAL_RADIUS * 0.8;
+        let points = vec![
+            (
+                center.0 + direction.0 * length,
+                center.1 + direction.1 * length,
+            ),
+            (center.0 + normal.0 * width, center.1 + normal.1 * width),
+            (center.0 - normal.0 * width, center.1 - normal.1 * width),
+        ]
+        .into_boxed_slice();
+        emitter.add_element(SvgElement::FillPolygon {
+            color: "#000".into(),
+            points,
+        });
+    }
+}
+
+/// 円を近似した多角形の頂点を返す。
+fn circle(center: (f64, f64), radius: f64) -> Box<[(f64, f64)]> {
+    (0..CIRCLE_SEGMENTS)
+        .map(|i| {
+            let angle = PI * 2.0 * i as f64 / CIRCLE_SEGMENTS as f64;
+            (
+                center.0 + radius * angle.cos(),
+                center.1 + radius * angle.sin(),
+            )
+        })
+        .collect::<Vec<_>>()
+        .into_boxed_slice()
+}
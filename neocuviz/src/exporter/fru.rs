@@ -1,11 +1,11 @@
 use super::{
+    orientation_marker,
     svg::{SvgElement, SvgEmitter},
     Exporter, ExporterParameters,
 };
 use crate::cube::{Cube, CubeFace};
 
 use std::{
-    collections::HashMap,
     f64::consts::FRAC_PI_6,
     io::{prelude::*, Result as IoResult},
 };
@@ -13,33 +13,107 @@ use std::{
 /// F, R, U 面が表示される `Exporter`。
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct Fru {
-    size: f64,
-    colors: HashMap<CubeFace, String>,
+    params: ExporterParameters,
+}
+
+/// 区切り線の区間 (始点, 終点, 描画するか)。
+type Segment = ((f64, f64), (f64, f64), bool);
+
+/// 描画される面の配置。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct FaceLayout {
+    /// 対象の面
+    pub(super) face: CubeFace,
+
+    /// 面の左上の座標
+    origin: (f64, f64),
+
+    /// 列が 1 つ進んだ時の差分
+    column_diff: (f64, f64),
+
+    /// 行が 1 つ進んだ時の差分
+    row_diff: (f64, f64),
+}
+
+impl FaceLayout {
+    /// 面上の (列, 行) 単位の座標を描画座標に変換する。
+    pub(super) fn point(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            self.origin.0 + self.column_diff.0 * x + self.row_diff.0 * y,
+            self.origin.1 + self.column_diff.1 * x + self.row_diff.1 * y,
+        )
+    }
+
+    /// `x` 列目の左側の区切り線を、(始点, 終点, 描画するか) の区間に分けて返す。
+    fn column_segments(&self, cube: &Cube, x: usize) -> Vec<Segment> {
+        let (rows, _) = cube.face_size(self.face);
+        (0..rows)
+            .map(|y| {
+                (
+                    self.point(x as f64, y as f64),
+                    self.point(x as f64, (y + 1) as f64),
+                    !is_fused(cube, self.face, (y, x - 1), (y, x)),
+                )
+            })
+            .collect()
+    }
+
+    /// `y` 行目の上側の区切り線を、(始点, 終点, 描画するか) の区間に分けて返す。
+    fn row_segments(&self, cube: &Cube, y: usize) -> Vec<Segment> {
+        let (_, columns) = cube.face_size(self.face);
+        (0..columns)
+            .map(|x| {
+                (
+                    self.point(x as f64, y as f64),
+                    self.point((x + 1) as f64, y as f64),
+                    !is_fused(cube, self.face, (y - 1, x), (y, x)),
+                )
+            })
+            .collect()
+    }
 }
 
 impl Exporter for Fru {
     fn set_params(&mut self, params: &ExporterParameters) {
-        self.colors = params.colors.clone();
-        self.size = params.size;
+        self.params = params.clone();
     }
 
     fn write(&self, cube: &Cube, writer: &mut dyn Write) -> IoResult<()> {
-        let mut emitter = SvgEmitter::new(self.size, self.size);
-        self.draw_faces(&mut emitter, cube)?;
-        self.draw_frame(&mut emitter, cube)?;
+        let mut emitter = SvgEmitter::new(self.params.size, self.params.size);
+        let layouts = Fru::layouts(cube);
+        self.draw_faces(&mut emitter, cube, &layouts)?;
+        self.draw_frame(&mut emitter, cube, &layouts)?;
         emitter.emit(writer)
     }
 }
 
 impl Fru {
-    fn draw_frame(&self, emitter: &mut SvgEmitter, cube: &Cube) -> IoResult<()> {
+    /// U, F, R 面の配置を求める。
+    fn layouts(cube: &Cube) -> [FaceLayout; 3] {
+        let (width, height, depth) = cube.dimensions();
+        isometric_layouts(width as f64, height as f64, depth as f64)
+    }
+
+    fn draw_frame(
+        &self,
+        emitter: &mut SvgEmitter,
+        cube: &Cube,
+        layouts: &[FaceLayout; 3],
+    ) -> IoResult<()> {
+        let [up, front, right] = layouts;
+        let (width, height, depth) = cube.dimensions();
+        let (width_f, height_f, depth_f) = (width as f64, height as f64, depth as f64);
+
         // 外枠
-        let points = (0..6)
-            .map(|i| {
-                let angle = FRAC_PI_6 * (i * 2 + 1) as f64;
-                (0.8 * angle.cos(), 0.8 * angle.sin())
-            })
-            .collect();
+        let points = vec![
+            up.point(0.0, 0.0),
+            up.point(width_f, 0.0),
+            right.point(depth_f, height_f),
+            right.point(0.0, height_f),
+            front.point(0.0, height_f),
+            front.point(0.0, 0.0),
+        ]
+        .into_boxed_slice();
         emitter.add_element(SvgElement::StrokePolygon {
             color: "#000".into(),
             thickness: 0.02,
@@ -47,164 +121,186 @@ impl Fru {
         });
 
         // 稜線
-        for i in 0..3 {
-            let angle = FRAC_PI_6 * (i * 4 + 1) as f64;
+        let corner = front.point(width_f, 0.0);
+        for &end in &[
+            front.point(0.0, 0.0),
+            up.point(width_f, 0.0),
+            front.point(width_f, height_f),
+        ] {
             emitter.add_element(SvgElement::Line {
                 color: "#000".into(),
                 thickness: 0.02,
-                start: (0.0, 0.0),
-                end: (0.8 * angle.cos(), 0.8 * angle.sin()),
+                start: corner,
+                end,
             });
         }
 
         // エッジライン
-        let part_length = 0.8 / cube.divisions() as f64;
-
+        // 融着されたピースの間の区間は描画しない
         // FU ペア
-        let upper = (0.8 * FRAC_PI_6.cos(), 0.8 * FRAC_PI_6.sin());
-        let lower = (0.0f64, -0.8);
-        for i in 0..(cube.divisions() - 1) {
-            let base_length = part_length * (i + 1) as f64;
-            let base_point = (
-                base_length * (FRAC_PI_6 * 5.0).cos(),
-                base_length * (FRAC_PI_6 * 5.0).sin(),
-            );
-            let points = vec![
-                (upper.0 + base_point.0, upper.1 + base_point.1),
-                base_point,
-                (lower.0 + base_point.0, lower.1 + base_point.1),
-            ]
-            .into_boxed_slice();
-            emitter.add_element(SvgElement::Polyline {
-                color: "#000".into(),
-                thickness: 0.02,
-                points,
-            })
+        for x in 1..width {
+            let mut segments = up.column_segments(cube, x);
+            segments.extend(front.column_segments(cube, x));
+            add_separators(emitter, &segments);
         }
 
         // UR ペア
-        let upper = (0.8 * (FRAC_PI_6 * 5.0).cos(), 0.8 * (FRAC_PI_6 * 5.0).sin());
-        let lower = (0.0f64, -0.8);
-        for i in 0..(cube.divisions() - 1) {
-            let base_length = part_length * (i + 1) as f64;
-            let base_point = (base_length * FRAC_PI_6.cos(), base_length * FRAC_PI_6.sin());
-            let points = vec![
-                (upper.0 + base_point.0, upper.1 + base_point.1),
-                base_point,
-                (lower.0 + base_point.0, lower.1 + base_point.1),
-            ]
-            .into_boxed_slice();
-            emitter.add_element(SvgElement::Polyline {
-                color: "#000".into(),
-                thickness: 0.02,
-                points,
-            })
+        for z in 1..depth {
+            let mut segments = up.row_segments(cube, z);
+            segments.extend(right.column_segments(cube, depth - z));
+            add_separators(emitter, &segments);
         }
 
         // FR ペア
-        let left = (0.8 * (FRAC_PI_6 * 5.0).cos(), 0.8 * (FRAC_PI_6 * 5.0).sin());
-        let right = (0.8 * FRAC_PI_6.cos(), 0.8 * FRAC_PI_6.sin());
-        for i in 0..(cube.divisions() - 1) {
-            let base_length = part_length * (i + 1) as f64;
-            let base_point = (0.0, -base_length);
-            let points = vec![
-                (left.0 + base_point.0, left.1 + base_point.1),
-                base_point,
-                (right.0 + base_point.0, right.1 + base_point.1),
-            ]
-            .into_boxed_slice();
-            emitter.add_element(SvgElement::Polyline {
-                color: "#000".into(),
-                thickness: 0.02,
-                points,
-            })
+        for y in 1..height {
+            let mut segments = front.row_segments(cube, y);
+            segments.extend(right.row_segments(cube, y));
+            add_separators(emitter, &segments);
         }
 
         Ok(())
     }
 
-    fn draw_faces(&self, emitter: &mut SvgEmitter, cube: &Cube) -> IoResult<()> {
-        let part_length = 0.8 / cube.divisions() as f64;
-        let left_diff = (
-            part_length * (FRAC_PI_6 * 7.0).cos(),
-            part_length * (FRAC_PI_6 * 7.0).sin(),
-        );
-        let right_diff = (
-            part_length * (FRAC_PI_6 * 11.0).cos(),
-            part_length * (FRAC_PI_6 * 11.0).sin(),
-        );
-        let down_diff = (0.0, -part_length);
-        let faces = cube.faces();
-
-        // U 面
-        for i in 0..9 {
-            let color = &self.colors[&faces[&CubeFace::Up][i]];
-            let (x, y) = (i % 3, i / 3);
-            let base = (
-                right_diff.0 * x as f64 + left_diff.0 * y as f64,
-                right_diff.1 * x as f64 + left_diff.1 * y as f64 + 0.8,
-            );
-
-            let points = vec![
-                base,
-                (base.0 + right_diff.0, base.1 + right_diff.1),
-                (base.0 + down_diff.0, base.1 + down_diff.1),
-                (base.0 + left_diff.0, base.1 + left_diff.1),
-            ]
-            .into_boxed_slice();
-            emitter.add_element(SvgElement::FillPolygon {
-                color: color.to_owned(),
-                points,
-            });
+    fn draw_faces(
+        &self,
+        emitter: &mut SvgEmitter,
+        cube: &Cube,
+        layouts: &[FaceLayout; 3],
+    ) -> IoResult<()> {
+        for layout in layouts {
+            self.draw_face(emitter, cube, layout);
         }
+        Ok(())
+    }
 
-        // F 面
-        for i in 0..9 {
-            let color = &self.colors[&faces[&CubeFace::Front][i]];
-            let (x, y) = (i % 3, i / 3);
-            let base = (
-                right_diff.0 * x as f64 + down_diff.0 * y as f64 + 0.8 * (FRAC_PI_6 * 5.0).cos(),
-                right_diff.1 * x as f64 + down_diff.1 * y as f64 + 0.8 * (FRAC_PI_6 * 5.0).sin(),
-            );
+    /// 1 つの面のステッカーを描画する。
+    fn draw_face(&self, emitter: &mut SvgEmitter, cube: &Cube, layout: &FaceLayout) {
+        let FaceLayout {
+            face,
+            column_diff,
+            row_diff,
+            ..
+        } = *layout;
+        let (_, columns) = cube.face_size(face);
+        for (i, sticker) in cube.faces()[&face].iter().enumerate() {
+            let color = self.params.sticker_color(sticker);
+            let (x, y) = (i % columns, i / columns);
+            let base = layout.point(x as f64, y as f64);
 
             let points = vec![
                 base,
-                (base.0 + right_diff.0, base.1 + right_diff.1),
+                (base.0 + column_diff.0, base.1 + column_diff.1),
                 (
-                    base.0 + down_diff.0 + right_diff.0,
-                    base.1 + down_diff.1 + right_diff.1,
+                    base.0 + column_diff.0 + row_diff.0,
+                    base.1 + column_diff.1 + row_diff.1,
                 ),
-                (base.0 + down_diff.0, base.1 + down_diff.1),
+                (base.0 + row_diff.0, base.1 + row_diff.1),
             ]
             .into_boxed_slice();
             emitter.add_element(SvgElement::FillPolygon {
-                color: color.to_owned(),
+                color: color.into(),
                 points,
             });
+
+            if let Some(orientation) = cube.orientation(face, y, x) {
+                let center = (
+                    base.0 + (column_diff.0 + row_diff.0) / 2.0,
+                    base.1 + (column_diff.1 + row_diff.1) / 2.0,
+                );
+                emitter.add_element(orientation_marker(
+                    center,
+                    column_diff,
+                    row_diff,
+                    orientation,
+                ));
+            }
         }
+    }
+}
 
-        // R 面
-        for i in 0..9 {
-            let color = &self.colors[&faces[&CubeFace::Right][i]];
-            let (x, y) = (i % 3, i / 3);
-            let base = (
-                -left_diff.0 * x as f64 + down_diff.0 * y as f64,
-                -left_diff.1 * x as f64 + down_diff.1 * y as f64,
-            );
+/// 幅, 高さ, 奥行きを単位数で与えた直方体の U, F, R 面の配置を求める。
+/// 全体の外接矩形が中央に来るように配置し、立方体の場合は半径 0.8 の六角形になる。
+pub(super) fn isometric_layouts(width: f64, height: f64, depth: f64) -> [FaceLayout; 3] {
+    let horizontal_units = (width + depth) * FRAC_PI_6.cos();
+    let vertical_units = (width + depth) * FRAC_PI_6.sin() + height;
+    let part_length = 1.6 / f64::max(horizontal_units, vertical_units);
 
-            let points = vec![
-                base,
-                (base.0 - left_diff.0, base.1 - left_diff.1),
-                (base.0 + right_diff.0, base.1 + right_diff.1),
-                (base.0 + down_diff.0, base.1 + down_diff.1),
-            ]
-            .into_boxed_slice();
-            emitter.add_element(SvgElement::FillPolygon {
-                color: color.to_owned(),
-                points,
+    let x_diff = (
+        part_length * (FRAC_PI_6 * 11.0).cos(),
+        part_length * (FRAC_PI_6 * 11.0).sin(),
+    );
+    let z_diff = (
+        part_length * (FRAC_PI_6 * 7.0).cos(),
+        part_length * (FRAC_PI_6 * 7.0).sin(),
+    );
+    let down_diff = (0.0, -part_length);
+
+    // 最も奥の頂点
+    let top = (
+        (depth - width) * FRAC_PI_6.cos() * part_length / 2.0,
+        vertical_units * part_length / 2.0,
+    );
+    let front_top_left = (top.0 + z_diff.0 * depth, top.1 + z_diff.1 * depth);
+    let front_top_right = (
+        front_top_left.0 + x_diff.0 * width,
+        front_top_left.1 + x_diff.1 * width,
+    );
+
+    [
+        FaceLayout {
+            face: CubeFace::Up,
+            origin: top,
+            column_diff: x_diff,
+            row_diff: z_diff,
+        },
+        FaceLayout {
+            face: CubeFace::Front,
+            origin: front_top_left,
+            column_diff: x_diff,
+            row_diff: down_diff,
+        },
+        FaceLayout {
+            face: CubeFace::Right,
+            origin: front_top_right,
+            column_diff: (-z_diff.0, -z_diff.1),
+            row_diff: down_diff,
+        },
+    ]
+}
+
+/// 同じ面上の 2 つのステッカーが融着されたピースに属するかどうかを返す。
+fn is_fused(cube: &Cube, face: CubeFace, a: (usize, usize), b: (usize, usize)) -> bool {
+    match (
+        cube.sticker_cubie(face, a.0, a.1),
+        cube.sticker_cubie(face, b.0, b.1),
+    ) {
+        (Some(a), Some(b)) => cube.is_fused(a, b),
+        _ => false,
+    }
+}
+
+/// 連続して描画される区間をまとめて折れ線として追加する。
+fn add_separators(emitter: &mut SvgEmitter, segments: &[Segment]) {
+    let mut points: Vec<(f64, f64)> = vec![];
+    for &(start, end, visible) in segments {
+        if visible {
+            if points.is_empty() {
+                points.push(start);
+            }
+            points.push(end);
+        } else if !points.is_empty() {
+            emitter.add_element(SvgElement::Polyline {
+                color: "#000".into(),
+                thickness: 0.02,
+                points: points.split_off(0).into_boxed_slice(),
             });
         }
-
-        Ok(())
+    }
+    if !points.is_empty() {
+        emitter.add_element(SvgElement::Polyline {
+            color: "#000".into(),
+            thickness: 0.02,
+            points: points.into_boxed_slice(),
+        });
     }
 }
@@ -0,0 +1,96 @@
+use super::{
+    svg::{SvgElement, SvgEmitter},
+    Exporter, ExporterParameters,
+};
+use crate::puzzle::{Fto, FtoFace, Puzzle, TRIANGLES};
+
+use std::{
+    f64::consts::SQRT_2,
+    io::{prelude::*, Result as IoResult},
+};
+
+/// ひし形の中心から角までの、縦横それぞれの距離。
+const HALF_WIDTH: f64 = 0.42;
+
+/// FTO の展開図を表示する `Exporter`。
+/// 正面の頂点の周りの 4 面を左、背面の頂点の周りの 4 面を右に、それぞれ外側から見たひし形として配置する。
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FtoNet {
+    params: ExporterParameters<FtoFace>,
+}
+
+impl Exporter<Fto> for FtoNet {
+    fn set_params(&mut self, params: &ExporterParameters<FtoFace>) {
+        self.params = params.clone();
+    }
+
+    fn write(&self, fto: &Fto, writer: &mut dyn Write) -> IoResult<()> {
+        let mut emitter = SvgEmitter::new(self.params.size, self.params.size);
+        for &face in &FtoFace::ALL {
+            self.draw_face(&mut emitter, fto, face);
+        }
+        emitter.emit(writer)
+    }
+}
+
+impl FtoNet {
+    /// 1 つの面のステッカーと区切り線を描画する。
+    fn draw_face(&self, emitter: &mut SvgEmitter, fto: &Fto, face: FtoFace) {
+        // 正面側は z 軸方向、背面側は反対方向から正射影する
+        let front = FtoFace::ALL[..4].contains(&face);
+        let (center, mirror) = if front { (-0.5, 1.0) } else { (0.5, -1.0) };
+        let positions: Vec<_> = face
+            .vertices()
+            .iter()
+            .map(|v| {
+                (
+                    center + v[0] * mirror * HALF_WIDTH * SQRT_2,
+                    v[1] * HALF_WIDTH * SQRT_2,
+                )
+            })
+            .collect();
+        let point = |(i, j): (usize, usize)| {
+            let (s, t) = (i as f64 / 3.0, j as f64 / 3.0);
+            (
+                positions[0].0
+                    + (positions[1].0 - positions[0].0) * s
+                    + (positions[2].0 - positions[0].0) * t,
+                positions[0].1
+                    + (positions[1].1 - positions[0].1) * s
+                    + (positions[2].1 - positions[0].1) * t,
+            )
+        };
+
+        for (sticker, triangle) in fto.stickers(face).iter().zip(TRIANGLES.iter()) {
+            let points = triangle
+                .iter()
+                .map(|&p| point(p))
+                .collect::<Vec<_>>()
+                .into_boxed_slice();
+            emitter.add_element(SvgElement::FillPolygon {
+                color: self.params.sticker_color(sticker).into(),
+                points,
+            });
+        }
+
+        // ステッカーの区切り
+        for k in 1..3 {
+            let lines = [((k, 0), (k, 3 - k)), ((0, k), (3 - k, k)), ((k, 0), (0, k))];
+            for &(start, end) in &lines {
+                emitter.add_element(SvgElement::Line {
+                    color: "#000".into(),
+                    thickness: 0.01,
+                    start: point(start),
+                    end: point(end),
+                });
+            }
+        }
+
+        // 外枠
+        emitter.add_element(SvgElement::StrokePolygon {
+            color: "#000".into(),
+            thickness: 0.02,
+            points: vec![point((0, 0)), point((3, 0)), point((0, 3))].into_boxed_slice(),
+        });
+    }
+}
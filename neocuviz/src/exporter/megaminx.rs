@@ -0,0 +1,203 @@
+use super::{
+    svg::{SvgElement, SvgEmitter},
+    Exporter, ExporterParameters,
+};
+use crate::puzzle::{megaminx_sticker_weights, Megaminx, MegaminxFace, Puzzle};
+
+use std::{
+    collections::HashMap,
+    f64::consts::PI,
+    io::{prelude::*, Result as IoResult},
+};
+
+/// 展開図上の頂点の座標。
+type Point2 = (f64, f64);
+
+/// 展開図の配置。(面, 隣接して配置される親の面) を配置する順に並べる。
+/// U 面と D 面をそれぞれ中心とした 2 つの花形を F 面と DR 面で繋ぐ。
+const NET_TREE: [(MegaminxFace, MegaminxFace); 11] = [
+    (MegaminxFace::Front, MegaminxFace::Up),
+    (MegaminxFace::Right, MegaminxFace::Up),
+    (MegaminxFace::BackRight, MegaminxFace::Up),
+    (MegaminxFace::BackLeft, MegaminxFace::Up),
+    (MegaminxFace::Left, MegaminxFace::Up),
+    (MegaminxFace::DownRight, MegaminxFace::Front),
+    (MegaminxFace::Down, MegaminxFace::DownRight),
+    (MegaminxFace::DownBackRight, MegaminxFace::Down),
+    (MegaminxFace::Back, MegaminxFace::Down),
+    (MegaminxFace::DownBackLeft, MegaminxFace::Down),
+    (MegaminxFace::DownLeft, MegaminxFace::Down),
+];
+
+/// 上から見たメガミンクスの U 面とその周囲の色を表示する `Exporter`。
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MegaminxTop {
+    params: ExporterParameters<MegaminxFace>,
+}
+
+impl Exporter<Megaminx> for MegaminxTop {
+    fn set_params(&mut self, params: &ExporterParameters<MegaminxFace>) {
+        self.params = params.clone();
+    }
+
+    fn write(&self, megaminx: &Megaminx, writer: &mut dyn Write) -> IoResult<()> {
+        let mut emitter = SvgEmitter::new(self.params.size, self.params.size);
+        let shapes = megaminx_sticker_weights();
+
+        // U 面と上側の 5 面のうち、U 層のステッカーを真上から正射影する
+        let mut polygons = vec![];
+        for &face in &MegaminxFace::ALL[..6] {
+            let vertices: Vec<Point2> = face.vertices().iter().map(|v| (v[0], -v[2])).collect();
+            for (index, (sticker, shape)) in megaminx.stickers(face).iter().zip(&shapes).enumerate()
+            {
+                if face == MegaminxFace::Up || is_top_layer(face, index) {
+                    polygons.push((sticker, sticker_points(&vertices, shape)));
+                }
+            }
+        }
+
+        // 外周が半径 0.96 の円に収まるように拡大する
+        let radius = polygons
+            .iter()
+            .flat_map(|(_, points)| points.iter())
+            .map(|(x, y)| x.hypot(*y))
+            .fold(0.0, f64::max);
+        let scale = 0.96 / radius;
+        for (sticker, points) in polygons {
+            let points = points.iter().map(|(x, y)| (x * scale, y * scale)).collect();
+            emitter.add_element(SvgElement::StrokeFillPolygon {
+                stroke_color: "#000".into(),
+                fill_color: self.params.sticker_color(sticker).into(),
+                thickness: 0.015,
+                points,
+            });
+        }
+        emitter.emit(writer)
+    }
+}
+
+/// メガミンクスの展開図を表示する `Exporter`。
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MegaminxNet {
+    params: ExporterParameters<MegaminxFace>,
+}
+
+impl Exporter<Megaminx> for MegaminxNet {
+    fn set_params(&mut self, params: &ExporterParameters<MegaminxFace>) {
+        self.params = params.clone();
+    }
+
+    fn write(&self, megaminx: &Megaminx, writer: &mut dyn Write) -> IoResult<()> {
+        let mut emitter = SvgEmitter::new(self.params.size, self.params.size);
+        let shapes = megaminx_sticker_weights();
+        let layout = net_layout();
+
+        // 全体の外接矩形が中央に来るように配置する
+        let points = layout.values().flatten();
+        let (min_x, max_x, min_y, max_y) = points.fold(
+            (f64::MAX, f64::MIN, f64::MAX, f64::MIN),
+            |(min_x, max_x, min_y, max_y), &(x, y)| {
+                (min_x.min(x), max_x.max(x), min_y.min(y), max_y.max(y))
+            },
+        );
+        let scale = 1.9 / f64::max(max_x - min_x, max_y - min_y);
+        let center = ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+
+        for face in &MegaminxFace::ALL {
+            let vertices: Vec<Point2> = layout[face]
+                .iter()
+                .map(|&(x, y)| ((x - center.0) * scale, (y - center.1) * scale))
+                .collect();
+            for (sticker, shape) in megaminx.stickers(*face).iter().zip(&shapes) {
+                emitter.add_element(SvgElement::StrokeFillPolygon {
+                    stroke_color: "#000".into(),
+                    fill_color: self.params.sticker_color(sticker).into(),
+                    thickness: 0.01,
+                    points: sticker_points(&vertices, shape),
+                });
+            }
+        }
+        emitter.emit(writer)
+    }
+}
+
+/// 面の周囲のステッカーのうち、U 層に含まれるものかどうかを返す。
+/// U 面と共有する 2 頂点の角と、その間の辺が該当する。
+fn is_top_layer(face: MegaminxFace, index: usize) -> bool {
+    let up = MegaminxFace::Up.vertices();
+    let vertices = face.vertices();
+    let shared = |i: usize| up.iter().any(|u| distance(*u, vertices[i % 5]) < 1e-6);
+    match index {
+        0 => false,
+        i if i % 2 == 1 => shared((i - 1) / 2),
+        i => shared((i - 2) / 2) && shared((i - 2) / 2 + 1),
+    }
+}
+
+/// 各面の頂点の展開図上の座標を求める。
+/// U 面を真上から見た配置を基準に、隣接する面を共有する辺で開いていく。
+fn net_layout() -> HashMap<MegaminxFace, [Point2; 5]> {
+    let mut layout = HashMap::new();
+    let up = MegaminxFace::Up.vertices();
+    let mut up_points = [(0.0, 0.0); 5];
+    for (point, vertex) in up_points.iter_mut().zip(up.iter()) {
+        *point = (vertex[0], -vertex[2]);
+    }
+    layout.insert(MegaminxFace::Up, up_points);
+
+    for &(face, parent) in &NET_TREE {
+        let vertices = face.vertices();
+        let parent_vertices = parent.vertices();
+        let position = |i: usize| {
+            parent_vertices
+                .iter()
+                .position(|p| distance(*p, vertices[i]) < 1e-6)
+                .map(|j| layout[&parent][j])
+        };
+
+        // 共有する辺の 2 頂点から残りの頂点を求める
+        let start = (0..5)
+            .find(|&i| position(i).is_some() && position((i + 1) % 5).is_some())
+            .expect("Faces in the tree should be adjacent");
+        let a: Point2 = position(start).expect("Vertex should be shared");
+        let b: Point2 = position((start + 1) % 5).expect("Vertex should be shared");
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let apothem = 0.5 / (PI / 5.0).tan();
+        let center = (
+            (a.0 + b.0) / 2.0 - dy * apothem,
+            (a.1 + b.1) / 2.0 + dx * apothem,
+        );
+
+        let mut points = [(0.0, 0.0); 5];
+        for k in 0..5 {
+            let angle = PI * 2.0 / 5.0 * k as f64;
+            let (sin, cos) = angle.sin_cos();
+            let (rx, ry) = (a.0 - center.0, a.1 - center.1);
+            points[(start + k) % 5] = (
+                center.0 + rx * cos - ry * sin,
+                center.1 + rx * sin + ry * cos,
+            );
+        }
+        layout.insert(face, points);
+    }
+    layout
+}
+
+/// 頂点に対する重みの組から、ステッカーの多角形の頂点を求める。
+fn sticker_points(vertices: &[Point2], shape: &[[f64; 5]]) -> Box<[Point2]> {
+    shape
+        .iter()
+        .map(|weights| {
+            vertices
+                .iter()
+                .zip(weights.iter())
+                .fold((0.0, 0.0), |(x, y), (v, w)| (x + v.0 * w, y + v.1 * w))
+        })
+        .collect::<Vec<_>>()
+        .into_boxed_slice()
+}
+
+/// 2 点間の距離を求める。
+fn distance(a: [f64; 3], b: [f64; 3]) -> f64 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
@@ -1,25 +1,103 @@
+mod clock;
 mod fru;
+mod fto;
+mod megaminx;
+mod pyraminx;
+mod skewb;
+mod square1;
 mod svg;
 mod top_layer;
 
+pub use clock::ClockFaces;
 pub use fru::Fru;
+pub use fto::FtoNet;
+pub use megaminx::{MegaminxNet, MegaminxTop};
+pub use pyraminx::PyraminxNet;
+pub use skewb::SkewbFru;
+pub use square1::Square1Layers;
 pub use top_layer::TopLayer;
 
-use crate::cube::{Cube, CubeFace};
-use std::{collections::HashMap, io::{prelude::*, Result as IoResult}};
+use self::svg::SvgElement;
+use crate::{
+    cube::{Cube, CubeFace, Sticker},
+    puzzle::Puzzle,
+};
+use std::{collections::HashMap, hash::Hash, io::{prelude::*, Result as IoResult}};
 
 /// Exporter に提供される共通パラメーター
-#[derive(Debug, Default, Clone, PartialEq)]
-pub struct ExporterParameters {
-    pub colors: HashMap<CubeFace, String>,
+/// `F` は描画するパズルの面を表す。
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExporterParameters<F: Eq + Hash = CubeFace> {
+    pub colors: HashMap<F, String>,
+    pub unknown_color: String,
     pub size: f64,
 }
 
+impl<F: Eq + Hash> Default for ExporterParameters<F> {
+    fn default() -> ExporterParameters<F> {
+        ExporterParameters {
+            colors: HashMap::new(),
+            unknown_color: String::new(),
+            size: 0.0,
+        }
+    }
+}
+
+impl<F: Eq + Hash> ExporterParameters<F> {
+    /// ステッカーの描画色を返す。
+    pub fn sticker_color<'a>(&'a self, sticker: &'a Sticker<F>) -> &'a str {
+        match sticker {
+            Sticker::Face(face) => &self.colors[face],
+            Sticker::Unknown => &self.unknown_color,
+            Sticker::Custom(color) => color,
+        }
+    }
+}
+
 /// SVG を出力する構造体が実装するべきトレイト。
-pub trait Exporter {
+/// `P` は描画するパズルを表す。
+pub trait Exporter<P: Puzzle = Cube> {
     /// 共通パラメーターを設定する。
-    fn set_params(&mut self, params: &ExporterParameters);
+    fn set_params(&mut self, params: &ExporterParameters<P::Face>);
 
     /// SVG を書き出す。
-    fn write(&self, cube: &Cube, writer: &mut dyn Write) -> IoResult<()>;
+    fn write(&self, puzzle: &P, writer: &mut dyn Write) -> IoResult<()>;
+}
+
+/// ステッカーの向きを表す三角形のマーカーを生成する。
+///
+/// * `center`: ステッカーの中心座標
+/// * `column_diff`: ステッカーの列方向のベクトル
+/// * `row_diff`: ステッカーの行方向のベクトル
+/// * `orientation`: 上方向を 0 とした時計回りの向き
+fn orientation_marker(
+    center: (f64, f64),
+    column_diff: (f64, f64),
+    row_diff: (f64, f64),
+    orientation: u8,
+) -> SvgElement {
+    let (forward, side) = match orientation % 4 {
+        0 => ((-row_diff.0, -row_diff.1), column_diff),
+        1 => (column_diff, row_diff),
+        2 => (row_diff, (-column_diff.0, -column_diff.1)),
+        _ => ((-column_diff.0, -column_diff.1), (-row_diff.0, -row_diff.1)),
+    };
+
+    let points = vec![
+        (center.0 + forward.0 * 0.35, center.1 + forward.1 * 0.35),
+        (
+            center.0 - forward.0 * 0.15 + side.0 * 0.2,
+            center.1 - forward.1 * 0.15 + side.1 * 0.2,
+        ),
+        (
+            center.0 - forward.0 * 0.15 - side.0 * 0.2,
+            center.1 - forward.1 * 0.15 - side.1 * 0.2,
+        ),
+    ]
+    .into_boxed_slice();
+    SvgElement::FillPolygon {
+        color: "#000".into(),
+        points,
+    }
 }
@@ -0,0 +1,97 @@
+use super::{
+    svg::{SvgElement, SvgEmitter},
+    Exporter, ExporterParameters,
+};
+use crate::puzzle::{Puzzle, Pyraminx, PyraminxFace, PyraminxVertex, TRIANGLES};
+
+use std::{
+    f64::consts::FRAC_PI_6,
+    io::{prelude::*, Result as IoResult},
+};
+
+/// ピラミンクスの展開図を表示する `Exporter`。
+/// F 面を中央に置き、L, R, D 面をその左右と下に配置する。
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PyraminxNet {
+    params: ExporterParameters<PyraminxFace>,
+}
+
+/// 面と面の間の隙間。
+const GAP: f64 = 0.06;
+
+impl Exporter<Pyraminx> for PyraminxNet {
+    fn set_params(&mut self, params: &ExporterParameters<PyraminxFace>) {
+        self.params = params.clone();
+    }
+
+    fn write(&self, pyraminx: &Pyraminx, writer: &mut dyn Write) -> IoResult<()> {
+        let mut emitter = SvgEmitter::new(self.params.size, self.params.size);
+        for &face in &PyraminxFace::ALL {
+            self.draw_face(&mut emitter, pyraminx, face);
+        }
+        emitter.emit(writer)
+    }
+}
+
+impl PyraminxNet {
+    /// 1 つの面のステッカーを描画する。
+    fn draw_face(&self, emitter: &mut SvgEmitter, pyraminx: &Pyraminx, face: PyraminxFace) {
+        let vertices = face.vertices();
+        let positions: Vec<_> = vertices
+            .iter()
+            .map(|&vertex| net_position(face, vertex))
+            .collect();
+        let point = |(i, j): (usize, usize)| {
+            let (s, t) = (i as f64 / 3.0, j as f64 / 3.0);
+            (
+                positions[0].0
+                    + (positions[1].0 - positions[0].0) * s
+                    + (positions[2].0 - positions[0].0) * t,
+                positions[0].1
+                    + (positions[1].1 - positions[0].1) * s
+                    + (positions[2].1 - positions[0].1) * t,
+            )
+        };
+
+        for (sticker, triangle) in pyraminx.stickers(face).iter().zip(TRIANGLES.iter()) {
+            let points = triangle
+                .iter()
+                .map(|&p| point(p))
+                .collect::<Vec<_>>()
+                .into_boxed_slice();
+            emitter.add_element(SvgElement::StrokeFillPolygon {
+                stroke_color: "#000".into(),
+                fill_color: self.params.sticker_color(sticker).into(),
+                thickness: 0.02,
+                points,
+            });
+        }
+    }
+}
+
+/// 展開図上での頂点の座標を返す。
+/// 1 辺の長さを 0.9 とし、全体の外接矩形が中央に来るように配置する。
+fn net_position(face: PyraminxFace, vertex: PyraminxVertex) -> (f64, f64) {
+    let side = 0.9;
+    let height = side * FRAC_PI_6.cos();
+    let (x, y) = match vertex {
+        PyraminxVertex::Up => (0.0, height / 2.0),
+        PyraminxVertex::Left => (-side / 2.0, -height / 2.0),
+        PyraminxVertex::Right => (side / 2.0, -height / 2.0),
+        // B 頂点は面ごとに展開先が異なる
+        PyraminxVertex::Back => match face {
+            PyraminxFace::Left => (-side, height / 2.0),
+            PyraminxFace::Right => (side, height / 2.0),
+            _ => (0.0, -height * 1.5),
+        },
+    };
+
+    // F 面以外は共有する辺から離れる方向にずらす
+    let (dx, dy) = match face {
+        PyraminxFace::Front => (0.0, 0.0),
+        PyraminxFace::Left => (-GAP * FRAC_PI_6.cos(), GAP * FRAC_PI_6.sin()),
+        PyraminxFace::Right => (GAP * FRAC_PI_6.cos(), GAP * FRAC_PI_6.sin()),
+        PyraminxFace::Down => (0.0, -GAP),
+    };
+    (x + dx, y + dy + height / 2.0 + GAP / 4.0)
+}
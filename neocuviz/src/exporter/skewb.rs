@@ -0,0 +1,52 @@
+use super::{
+    fru::isometric_layouts,
+    svg::{SvgElement, SvgEmitter},
+    Exporter, ExporterParameters,
+};
+use crate::puzzle::{Puzzle, Skewb, SKEWB_STICKERS};
+
+use std::io::{prelude::*, Result as IoResult};
+
+/// スキューブの F, R, U 面を `Fru` と同じ配置で表示する `Exporter`。
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SkewbFru {
+    params: ExporterParameters,
+}
+
+impl Exporter<Skewb> for SkewbFru {
+    fn set_params(&mut self, params: &ExporterParameters) {
+        self.params = params.clone();
+    }
+
+    fn write(&self, skewb: &Skewb, writer: &mut dyn Write) -> IoResult<()> {
+        let mut emitter = SvgEmitter::new(self.params.size, self.params.size);
+        for layout in &isometric_layouts(1.0, 1.0, 1.0) {
+            for (sticker, &(r, d)) in skewb
+                .stickers(layout.face)
+                .iter()
+                .zip(SKEWB_STICKERS.iter())
+            {
+                // 面を 0.0 から 1.0 の範囲とした時の頂点
+                let (r, d) = (r as f64, d as f64);
+                let vertices = if r == 0.0 && d == 0.0 {
+                    vec![(0.5, 0.0), (1.0, 0.5), (0.5, 1.0), (0.0, 0.5)]
+                } else {
+                    let corner = ((r + 1.0) / 2.0, (d + 1.0) / 2.0);
+                    vec![corner, (0.5, corner.1), (corner.0, 0.5)]
+                };
+                let points = vertices
+                    .into_iter()
+                    .map(|(x, y)| layout.point(x, y))
+                    .collect::<Vec<_>>()
+                    .into_boxed_slice();
+                emitter.add_element(SvgElement::StrokeFillPolygon {
+                    stroke_color: "#000".into(),
+                    fill_color: self.params.sticker_color(sticker).into(),
+                    thickness: 0.02,
+                    points,
+                });
+            }
+        }
+        emitter.emit(writer)
+    }
+}
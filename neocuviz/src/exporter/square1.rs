@@ -0,0 +1,143 @@
+use super::{
+    svg::{SvgElement, SvgEmitter},
+    Exporter, ExporterParameters,
+};
+use crate::{
+    cube::{CubeFace, Sticker},
+    puzzle::{Puzzle, Square1, Square1Part},
+};
+
+use std::{
+    f64::consts::PI,
+    io::{prelude::*, Result as IoResult},
+};
+
+/// 揃った状態の層の外周の正方形の半分の幅。
+const HALF_WIDTH: f64 = 0.3;
+
+/// 側面のステッカーの幅の割合。
+const SIDE_RATIO: f64 = 0.18;
+
+/// Square-1 の U 層と D 層を左右に並べて表示する `Exporter`。
+/// D 層は F 面が上になるように下から見た状態で描画し、その下に中層の F 面側を描画する。
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Square1Layers {
+    params: ExporterParameters,
+}
+
+impl Exporter<Square1> for Square1Layers {
+    fn set_params(&mut self, params: &ExporterParameters) {
+        self.params = params.clone();
+    }
+
+    fn write(&self, square1: &Square1, writer: &mut dyn Write) -> IoResult<()> {
+        let mut emitter = SvgEmitter::new(self.params.size, self.params.size);
+        self.draw_layer(&mut emitter, square1, CubeFace::Up, (-0.5, 0.15));
+        self.draw_layer(&mut emitter, square1, CubeFace::Down, (0.5, 0.15));
+        self.draw_middle(&mut emitter, square1);
+        emitter.emit(writer)
+    }
+}
+
+impl Square1Layers {
+    /// 1 つの層のピースを描画する。
+    fn draw_layer(
+        &self,
+        emitter: &mut SvgEmitter,
+        square1: &Square1,
+        face: CubeFace,
+        center: (f64, f64),
+    ) {
+        // 上から見て F 方向から時計回りの角度の方向にある、中心から distance の位置
+        let point = |degrees: f64, distance: f64| {
+            let (sin, cos) = (degrees * PI / 180.0).sin_cos();
+            let y = if face == CubeFace::Up { -cos } else { cos };
+            (center.0 + sin * distance, center.1 + y * distance)
+        };
+        let edge_distance = HALF_WIDTH / (PI / 12.0).cos();
+        let corner_distance = HALF_WIDTH * 2.0f64.sqrt();
+
+        let stickers = square1.stickers(face);
+        let sides = square1.side_stickers(face);
+        for (i, part) in square1.parts(face).into_iter().enumerate() {
+            let start = 15.0 + 30.0 * i as f64;
+            let outlines: Vec<_> = match part {
+                Square1Part::Edge => vec![(
+                    point(start, edge_distance),
+                    point(start + 30.0, edge_distance),
+                    &sides[i],
+                )],
+                Square1Part::CornerFirst => vec![
+                    (
+                        point(start, edge_distance),
+                        point(start + 30.0, corner_distance),
+                        &sides[i],
+                    ),
+                    (
+                        point(start + 30.0, corner_distance),
+                        point(start + 60.0, edge_distance),
+                        &sides[(i + 1) % sides.len()],
+                    ),
+                ],
+                Square1Part::CornerSecond => continue,
+            };
+
+            let mut points = vec![center];
+            points.extend(outlines.iter().map(|(start, _, _)| *start));
+            points.push(outlines[outlines.len() - 1].1);
+            self.add_polygon(emitter, &stickers[i], points);
+
+            // 側面のステッカーは外周を中心から離れる方向に広げて描画する
+            for &(start, end, sticker) in &outlines {
+                let outer = |(x, y): (f64, f64)| {
+                    (
+                        center.0 + (x - center.0) * (1.0 + SIDE_RATIO),
+                        center.1 + (y - center.1) * (1.0 + SIDE_RATIO),
+                    )
+                };
+                self.add_polygon(emitter, sticker, vec![start, end, outer(end), outer(start)]);
+            }
+        }
+
+        // スライスの切れ目
+        let cut = HALF_WIDTH * (1.0 + SIDE_RATIO) * 1.6;
+        emitter.add_element(SvgElement::Line {
+            color: "#000".into(),
+            thickness: 0.02,
+            start: point(15.0, cut),
+            end: point(195.0, cut),
+        });
+    }
+
+    /// 中層の F 面側を描画する。
+    /// 切れ目は F 面の中央より右にあり、右半分が反転している時は B 面の色になる。
+    fn draw_middle(&self, emitter: &mut SvgEmitter, square1: &Square1) {
+        let (top, bottom) = (-0.55, -0.7);
+        let (left, right) = (-0.5, 0.5);
+        let cut = (right + left) / 2.0 + (right - left) / 2.0 * (PI / 12.0).tan();
+        let right_face = if square1.is_middle_flipped() {
+            CubeFace::Back
+        } else {
+            CubeFace::Front
+        };
+
+        let rectangle = |left: f64, right: f64| {
+            vec![(left, top), (right, top), (right, bottom), (left, bottom)]
+        };
+        self.add_polygon(
+            emitter,
+            &Sticker::Face(CubeFace::Front),
+            rectangle(left, cut),
+        );
+        self.add_polygon(emitter, &Sticker::Face(right_face), rectangle(cut, right));
+    }
+
+    fn add_polygon(&self, emitter: &mut SvgEmitter, sticker: &Sticker, points: Vec<(f64, f64)>) {
+        emitter.add_element(SvgElement::StrokeFillPolygon {
+            stroke_color: "#000".into(),
+            fill_color: self.params.sticker_color(sticker).into(),
+            thickness: 0.015,
+            points: points.into_boxed_slice(),
+        });
+    }
+}
@@ -1,29 +1,25 @@
 use super::{
+    orientation_marker,
     svg::{SvgElement, SvgEmitter},
     Exporter, ExporterParameters,
 };
 use crate::cube::{Cube, CubeFace};
 
-use std::{
-    collections::HashMap,
-    io::{prelude::*, Result as IoResult},
-};
+use std::io::{prelude::*, Result as IoResult};
 
 /// 上面とその周囲の色を表示する `Exporter`。
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct TopLayer {
-    size: f64,
-    colors: HashMap<CubeFace, String>,
+    params: ExporterParameters,
 }
 
 impl Exporter for TopLayer {
     fn set_params(&mut self, params: &ExporterParameters) {
-        self.colors = params.colors.clone();
-        self.size = params.size;
+        self.params = params.clone();
     }
 
     fn write(&self, cube: &Cube, writer: &mut dyn Write) -> IoResult<()> {
-        let mut emitter = SvgEmitter::new(self.size, self.size);
+        let mut emitter = SvgEmitter::new(self.params.size, self.params.size);
         self.draw(&mut emitter, cube)?;
         emitter.emit(writer)
     }
@@ -31,26 +27,93 @@ impl Exporter for TopLayer {
 
 impl TopLayer {
     fn draw(&self, emitter: &mut SvgEmitter, cube: &Cube) -> IoResult<()> {
+        let (rows, columns) = cube.face_size(CubeFace::Up);
         let faces = cube.faces();
         let top = &faces[&CubeFace::Up];
-        for i in 0..(cube.divisions().pow(2)) {
-            let (x, y) = (i % 3, i / 3);
-            let color = &self.colors[&top[i]];
-            let half = (cube.divisions() as f64 - 1.0) / 2.0;
+        let unit = 0.96 / rows.max(columns) as f64;
+        let half_x = (columns as f64 - 1.0) / 2.0;
+        let half_y = (rows as f64 - 1.0) / 2.0;
+        let corner = |x: usize, y: usize| {
+            (
+                (x as f64 - half_x - 0.5) * unit,
+                (half_y - y as f64 + 0.5) * unit,
+            )
+        };
+        let is_bandaged = |x: usize, y: usize| {
+            cube.sticker_cubie(CubeFace::Up, y, x)
+                .map(|c| cube.is_bandaged(c))
+                .unwrap_or(false)
+        };
+
+        // 融着されたピースは枠線を別に描画するため、先に塗り潰しておく
+        for (i, sticker) in top.iter().enumerate() {
+            let (x, y) = (i % columns, i / columns);
+            if !is_bandaged(x, y) {
+                continue;
+            }
             let points = vec![
-                ((x as f64 - half - 0.5) * 0.32, (half - y as f64 + 0.5) * 0.32),
-                ((x as f64 - half + 0.5) * 0.32, (half - y as f64 + 0.5) * 0.32),
-                ((x as f64 - half + 0.5) * 0.32, (half - y as f64 - 0.5) * 0.32),
-                ((x as f64 - half - 0.5) * 0.32, (half - y as f64 - 0.5) * 0.32),
+                corner(x, y),
+                corner(x + 1, y),
+                corner(x + 1, y + 1),
+                corner(x, y + 1),
             ]
             .into_boxed_slice();
-
-            emitter.add_element(SvgElement::StrokeFillPolygon {
-                stroke_color: "#000".into(),
-                fill_color: color.to_owned(),
-                thickness: 0.02,
+            emitter.add_element(SvgElement::FillPolygon {
+                color: self.params.sticker_color(sticker).into(),
                 points,
-            })
+            });
+        }
+
+        for (i, sticker) in top.iter().enumerate() {
+            let (x, y) = (i % columns, i / columns);
+            if is_bandaged(x, y) {
+                // 同じブロックに属するステッカーとの間には枠線を描画しない
+                let edges = [
+                    (corner(x, y), corner(x + 1, y), y.checked_sub(1).map(|ny| (x, ny))),
+                    (corner(x + 1, y), corner(x + 1, y + 1), Some((x + 1, y))),
+                    (corner(x + 1, y + 1), corner(x, y + 1), Some((x, y + 1))),
+                    (corner(x, y + 1), corner(x, y), x.checked_sub(1).map(|nx| (nx, y))),
+                ];
+                for &(start, end, neighbor) in &edges {
+                    let fused = neighbor
+                        .and_then(|(nx, ny)| cube.sticker_cubie(CubeFace::Up, ny, nx))
+                        .zip(cube.sticker_cubie(CubeFace::Up, y, x))
+                        .map(|(n, c)| cube.is_fused(n, c))
+                        .unwrap_or(false);
+                    if !fused {
+                        emitter.add_element(SvgElement::Line {
+                            color: "#000".into(),
+                            thickness: 0.02,
+                            start,
+                            end,
+                        });
+                    }
+                }
+            } else {
+                let points = vec![
+                    corner(x, y),
+                    corner(x + 1, y),
+                    corner(x + 1, y + 1),
+                    corner(x, y + 1),
+                ]
+                .into_boxed_slice();
+                emitter.add_element(SvgElement::StrokeFillPolygon {
+                    stroke_color: "#000".into(),
+                    fill_color: self.params.sticker_color(sticker).into(),
+                    thickness: 0.02,
+                    points,
+                });
+            }
+
+            if let Some(orientation) = cube.orientation(CubeFace::Up, y, x) {
+                let center = ((x as f64 - half_x) * unit, (half_y - y as f64) * unit);
+                emitter.add_element(orientation_marker(
+                    center,
+                    (unit, 0.0),
+                    (0.0, -unit),
+                    orientation,
+                ));
+            }
         }
 
         Ok(())
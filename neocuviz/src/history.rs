@@ -0,0 +1,118 @@
+use crate::{
+    cube::{Cube, CubeError},
+    notation::Movement,
+};
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+/// 操作履歴上の位置を表す。
+/// 位置は適用済みの手数で表されるため、取り消した後に別の操作を適用すると以前とは異なる状態を指す。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Checkpoint(usize);
+
+/// 適用した操作を記録し、取り消しとやり直しができるキューブ。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct History {
+    cube: Cube,
+    applied: Vec<Movement>,
+    undone: Vec<Movement>,
+}
+
+impl History {
+    /// 指定した状態から履歴の記録を始める。
+    pub fn new(cube: Cube) -> History {
+        History {
+            cube,
+            applied: vec![],
+            undone: vec![],
+        }
+    }
+
+    /// 現在の状態を返す。
+    pub fn cube(&self) -> &Cube {
+        &self.cube
+    }
+
+    /// 現在の状態を取り出す。
+    pub fn into_cube(self) -> Cube {
+        self.cube
+    }
+
+    /// 適用済みの操作を返す。
+    pub fn movements(&self) -> &[Movement] {
+        &self.applied
+    }
+
+    /// 操作を適用して履歴に追加する。
+    /// やり直し可能な操作は破棄される。
+    pub fn apply(&mut self, movement: Movement) -> Result<(), CubeError> {
+        self.cube.apply(movement)?;
+        self.applied.push(movement);
+        self.undone.clear();
+        Ok(())
+    }
+
+    /// 最後に適用した操作を取り消し、その操作を返す。
+    pub fn undo(&mut self) -> Option<Movement> {
+        let movement = self.applied.pop()?;
+        self.cube
+            .apply(movement.inverse())
+            .expect("Inverse of applied movement should be applicable");
+        self.undone.push(movement);
+        Some(movement)
+    }
+
+    /// 最後に取り消した操作をやり直し、その操作を返す。
+    pub fn redo(&mut self) -> Option<Movement> {
+        let movement = self.undone.pop()?;
+        self.cube
+            .apply(movement)
+            .expect("Undone movement should be applicable");
+        self.applied.push(movement);
+        Some(movement)
+    }
+
+    /// 取り消し可能かどうかを返す。
+    pub fn can_undo(&self) -> bool {
+        !self.applied.is_empty()
+    }
+
+    /// やり直し可能かどうかを返す。
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// 現在の位置を返す。
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.applied.len())
+    }
+
+    /// 取り消しとやり直しを繰り返して指定した位置に移動する。
+    /// 到達できない位置では移動せずに `false` を返す。
+    pub fn jump(&mut self, checkpoint: Checkpoint) -> bool {
+        let Checkpoint(position) = checkpoint;
+        if position > self.applied.len() + self.undone.len() {
+            return false;
+        }
+
+        while self.applied.len() > position {
+            self.undo();
+        }
+        while self.applied.len() < position {
+            self.redo();
+        }
+        true
+    }
+
+    /// 適用済みの操作を回転記号として返す。
+    pub fn notation(&self) -> String {
+        self.applied
+            .iter()
+            .map(|m| m.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
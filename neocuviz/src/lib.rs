@@ -1,4 +1,23 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 pub mod notation;
 pub mod cube;
+pub mod replay;
+pub mod history;
+pub mod algorithm;
+
+#[cfg(feature = "std")]
 pub mod exporter;
+#[cfg(feature = "std")]
 pub mod converter;
+#[cfg(feature = "std")]
+pub mod puzzle;
+#[cfg(feature = "std")]
+pub mod scramble;
+#[cfg(feature = "std")]
+pub mod solver;
+
+#[cfg(feature = "serde")]
+pub mod serialization;
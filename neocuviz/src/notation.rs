@@ -1,5 +1,4 @@
-use std::{
-    error::Error,
+use core::{
     fmt::{Display, Formatter, Result as FmtResult},
     iter::Peekable,
     str::Chars,
@@ -48,6 +47,11 @@ pub enum Face {
 
 /// 操作の回転方向を表す。
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
 pub enum Rotation {
     /// 時計回り
     Clockwise,
@@ -56,6 +60,7 @@ pub enum Rotation {
     Counterclockwise,
 
     /// 180 度 (2 回転)
+    /// ピラミンクスなど 3 回対称の軸の回転では `Counterclockwise` と同じ結果になる
     Turnover,
 }
 
@@ -90,22 +95,74 @@ impl Movement {
     }
 }
 
+impl Display for Movement {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let (face, layers) = match self.target {
+            Face::Front(layers) => ('F', Some(layers)),
+            Face::Standing => ('S', None),
+            Face::Back(layers) => ('B', Some(layers)),
+            Face::Left(layers) => ('L', Some(layers)),
+            Face::Middle => ('M', None),
+            Face::Right(layers) => ('R', Some(layers)),
+            Face::Up(layers) => ('U', Some(layers)),
+            Face::Equational => ('E', None),
+            Face::Down(layers) => ('D', Some(layers)),
+            Face::X => ('x', None),
+            Face::Y => ('y', None),
+            Face::Z => ('z', None),
+        };
+        match layers {
+            None | Some(1) => write!(f, "{}", face)?,
+            Some(2) => write!(f, "{}w", face)?,
+            Some(layers) => write!(f, "{}{}w", layers, face)?,
+        }
+        match self.direction {
+            Rotation::Clockwise => Ok(()),
+            Rotation::Counterclockwise => write!(f, "'"),
+            Rotation::Turnover => write!(f, "2"),
+        }
+    }
+}
+
 /// 回転記号のエラーを表す。
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MovementParseError {
     /// 不正な面表記
     InvalidFace(char),
+
+    /// 層数の後に面表記がない
+    UnexpectedEnd,
+
+    /// 面表記に続く回転方向の表記が不正
+    InvalidSuffix(char),
+
+    /// 前置された層数が大きすぎる
+    TooManyLayers,
+
+    /// 前置された層数が 0
+    ZeroLayers,
+
+    /// 層数を前置した大文字の面表記に w がない (SiGN の単一層回転は未対応)
+    MissingWide(char),
 }
 
 impl Display for MovementParseError {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         match self {
             MovementParseError::InvalidFace(face) => write!(f, "Invalid face notation: {}", face),
+            MovementParseError::UnexpectedEnd => write!(f, "Face notation expected after layers"),
+            MovementParseError::InvalidSuffix(c) => write!(f, "Invalid rotation suffix: {}", c),
+            MovementParseError::TooManyLayers => write!(f, "Layer count is too large"),
+            MovementParseError::ZeroLayers => write!(f, "Layer count must be positive"),
+            MovementParseError::MissingWide(face) => {
+                write!(f, "Wide notation expected after layer prefix: {}", face)
+            }
         }
     }
 }
 
-impl Error for MovementParseError {}
+#[cfg(feature = "std")]
+impl std::error::Error for MovementParseError {}
 
 /// 回転記号をパースして `Movement` を生成するイテレーター。
 #[derive(Debug)]
@@ -125,6 +182,40 @@ impl<'a> Movements<'a> {
             self.rest_notation.next();
         }
     }
+
+    /// 回転方向を読み取り、`Movement` を生成する。
+    fn finish_movement(&mut self, face: char, layers: usize) -> Movement {
+        self.skip_whitespaces();
+        let direction = match self.rest_notation.peek() {
+            Some('2') => {
+                self.rest_notation.next();
+                Rotation::Turnover
+            }
+            Some('\'') => {
+                self.rest_notation.next();
+                Rotation::Counterclockwise
+            }
+            _ => Rotation::Clockwise,
+        };
+
+        let target = match face {
+            'x' => Face::X,
+            'y' => Face::Y,
+            'z' => Face::Z,
+            'F' => Face::Front(layers),
+            'S' => Face::Standing,
+            'B' => Face::Back(layers),
+            'L' => Face::Left(layers),
+            'M' => Face::Middle,
+            'R' => Face::Right(layers),
+            'U' => Face::Up(layers),
+            'E' => Face::Equational,
+            'D' => Face::Down(layers),
+            _ => unreachable!("Unrecognized face"),
+        };
+
+        Movement { target, direction }
+    }
 }
 
 impl<'a> Iterator for Movements<'a> {
@@ -132,6 +223,41 @@ impl<'a> Iterator for Movements<'a> {
 
     fn next(&mut self) -> Option<Result<Movement, MovementParseError>> {
         self.skip_whitespaces();
+
+        // 3Rw や 3r のように層数を前置した場合は、その層数の回転とする
+        // SiGN の 3R (3 層目のみの回転) とは意味が異なるため、大文字の場合は w を必須とする
+        let mut prefixed_layers = None;
+        while let Some(digit) = self.rest_notation.peek().and_then(|c| c.to_digit(10)) {
+            self.rest_notation.next();
+            let layers = prefixed_layers
+                .unwrap_or(0usize)
+                .checked_mul(10)
+                .and_then(|l| l.checked_add(digit as usize));
+            match layers {
+                Some(layers) => prefixed_layers = Some(layers),
+                None => return Some(Err(MovementParseError::TooManyLayers)),
+            }
+        }
+        if let Some(prefixed_layers) = prefixed_layers {
+            if prefixed_layers == 0 {
+                return Some(Err(MovementParseError::ZeroLayers));
+            }
+            let face = match self.rest_notation.next() {
+                None => return Some(Err(MovementParseError::UnexpectedEnd)),
+                Some(face) => match face {
+                    'F' | 'B' | 'L' | 'R' | 'U' | 'D' => {
+                        if self.rest_notation.next_if_eq(&'w').is_none() {
+                            return Some(Err(MovementParseError::MissingWide(face)));
+                        }
+                        face
+                    }
+                    'f' | 'b' | 'l' | 'r' | 'u' | 'd' => face.to_ascii_uppercase(),
+                    _ => return Some(Err(MovementParseError::InvalidFace(face))),
+                },
+            };
+            return Some(Ok(self.finish_movement(face, prefixed_layers)));
+        }
+
         let (face, layers) = match self.rest_notation.next() {
             None => return None,
             Some(face) => match face {
@@ -156,35 +282,6 @@ impl<'a> Iterator for Movements<'a> {
             layers
         };
 
-        self.skip_whitespaces();
-        let direction = match self.rest_notation.peek() {
-            Some('2') => {
-                self.rest_notation.next();
-                Rotation::Turnover
-            }
-            Some('\'') => {
-                self.rest_notation.next();
-                Rotation::Counterclockwise
-            }
-            _ => Rotation::Clockwise,
-        };
-
-        let target = match face {
-            'x' => Face::X,
-            'y' => Face::Y,
-            'z' => Face::Z,
-            'F' => Face::Front(layers),
-            'S' => Face::Standing,
-            'B' => Face::Back(layers),
-            'L' => Face::Left(layers),
-            'M' => Face::Middle,
-            'R' => Face::Right(layers),
-            'U' => Face::Up(layers),
-            'E' => Face::Equational,
-            'D' => Face::Down(layers),
-            _ => unreachable!("Unrecognized face"),
-        };
-
-        Some(Ok(Movement { target, direction }))
+        Some(Ok(self.finish_movement(face, layers)))
     }
 }
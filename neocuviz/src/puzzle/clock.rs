@@ -0,0 +1,371 @@
+use super::Puzzle;
+use crate::cube::Sticker;
+use std::{
+    convert::Infallible,
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+/// 1 つの面のダイヤルの数。
+const DIALS_PER_FACE: usize = 9;
+
+/// 各ピンの周囲のダイヤルのインデックス。`ClockPins::to_array` の順に並べる。
+const PIN_DIALS: [[usize; 4]; 4] = [[0, 1, 3, 4], [1, 2, 4, 5], [3, 4, 6, 7], [4, 5, 7, 8]];
+
+/// 各ピンの位置の角のダイヤルのインデックス。
+const CORNER_DIALS: [usize; 4] = [0, 2, 6, 8];
+
+/// 1 つのピンの状態の略記と、それが表すピンの組。
+const PIN_NAMES: [(&str, [bool; 4]); 9] = [
+    ("UR", [false, true, false, false]),
+    ("DR", [false, false, false, true]),
+    ("DL", [false, false, true, false]),
+    ("UL", [true, false, false, false]),
+    ("U", [true, true, false, false]),
+    ("R", [false, true, false, true]),
+    ("D", [false, false, true, true]),
+    ("L", [true, false, true, false]),
+    ("ALL", [true, true, true, true]),
+];
+
+/// クロックの面を表す。
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ClockFace {
+    /// 最初に手前に向けている面
+    Front,
+
+    /// 最初に奥に向けている面
+    Back,
+}
+
+impl ClockFace {
+    /// 全ての面。
+    pub const ALL: [ClockFace; 2] = [ClockFace::Front, ClockFace::Back];
+}
+
+/// 手前の面から見た 4 つのピンの状態を表す。
+/// `true` のピンは手前に押し出されている。
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub struct ClockPins {
+    /// 左上のピン
+    pub up_left: bool,
+
+    /// 右上のピン
+    pub up_right: bool,
+
+    /// 左下のピン
+    pub down_left: bool,
+
+    /// 右下のピン
+    pub down_right: bool,
+}
+
+impl ClockPins {
+    /// 左上、右上、左下、右下の順に並べた配列から生成する。
+    pub fn from_array(pins: [bool; 4]) -> ClockPins {
+        ClockPins {
+            up_left: pins[0],
+            up_right: pins[1],
+            down_left: pins[2],
+            down_right: pins[3],
+        }
+    }
+
+    /// 左上、右上、左下、右下の順に並べた配列を返す。
+    pub fn to_array(self) -> [bool; 4] {
+        [self.up_left, self.up_right, self.down_left, self.down_right]
+    }
+
+    /// 裏側から見た状態を返す。左右が入れ替わり、押し出されているピンが反転する。
+    pub fn reversed(self) -> ClockPins {
+        ClockPins {
+            up_left: !self.up_right,
+            up_right: !self.up_left,
+            down_left: !self.down_right,
+            down_right: !self.down_left,
+        }
+    }
+
+    /// `UR` や `ALL` のような略記から生成する。
+    fn from_name(name: &str) -> Option<ClockPins> {
+        PIN_NAMES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|&(_, pins)| ClockPins::from_array(pins))
+    }
+
+    /// 押し出されているピンを個別に空白で区切って並べた表記を返す。
+    fn list(self) -> String {
+        let names: Vec<_> = ["UL", "UR", "DL", "DR"]
+            .iter()
+            .zip(self.to_array().iter())
+            .filter(|(_, &up)| up)
+            .map(|(name, _)| *name)
+            .collect();
+        names.join(" ")
+    }
+
+    /// 略記で表せる場合はその表記を返す。
+    fn name(self) -> Option<&'static str> {
+        let pins = self.to_array();
+        PIN_NAMES.iter().find(|(_, p)| *p == pins).map(|(n, _)| *n)
+    }
+}
+
+impl Display for ClockPins {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self.name() {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "{}", self.list()),
+        }
+    }
+}
+
+/// クロックの 1 つの操作を表す。
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum ClockMovement {
+    /// `UR3+` のような、ピンを設定してダイヤルを回す操作。
+    /// 回転量は 1 時間を単位とし、手前の面から見て時計回りを正とする
+    Turn { pins: ClockPins, amount: i8 },
+
+    /// `y2` による表裏の入れ替え
+    Flip,
+
+    /// 手順の最後の `UR DL` のような、ピンの状態の指定
+    SetPins(ClockPins),
+}
+
+impl ClockMovement {
+    /// 逆操作を返す。
+    /// ピンの状態の指定は元の状態に戻せないため、そのまま返す。
+    pub fn inverse(&self) -> ClockMovement {
+        match *self {
+            ClockMovement::Turn { pins, amount } => ClockMovement::Turn {
+                pins,
+                amount: -amount,
+            },
+            movement => movement,
+        }
+    }
+}
+
+impl Display for ClockMovement {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            ClockMovement::Turn { pins, amount } => {
+                let sign = if *amount < 0 { '-' } else { '+' };
+                write!(f, "{}{}{}", pins, amount.abs(), sign)
+            }
+            ClockMovement::Flip => write!(f, "y2"),
+            ClockMovement::SetPins(pins) => write!(f, "{}", pins.list()),
+        }
+    }
+}
+
+/// クロックの回転記号のエラーを表す。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClockParseError {
+    /// 不正な表記
+    InvalidToken(String),
+
+    /// 回転量が 0 から 6 の範囲外
+    InvalidAmount(String),
+}
+
+impl Display for ClockParseError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            ClockParseError::InvalidToken(token) => write!(f, "Invalid clock notation: {}", token),
+            ClockParseError::InvalidAmount(token) => write!(f, "Invalid turn amount: {}", token),
+        }
+    }
+}
+
+impl Error for ClockParseError {}
+
+/// WCA 記法のクロックの回転記号をパースする。
+/// `UR3+ DL2- ALL1+ y2 UR DL` のように空白で区切り、連続するピンのみの表記は 1 つの `SetPins` にまとめる。
+pub fn parse_clock_movements(source: &str) -> Result<Vec<ClockMovement>, ClockParseError> {
+    let mut movements = vec![];
+    for token in source.split_whitespace() {
+        if token == "y2" {
+            movements.push(ClockMovement::Flip);
+            continue;
+        }
+
+        // 単独のピンは直前のピンの指定に追加する
+        if let Some(pin) = ["UL", "UR", "DL", "DR"].iter().position(|&p| p == token) {
+            let mut pins = match movements.last() {
+                Some(ClockMovement::SetPins(pins)) => {
+                    let pins = pins.to_array();
+                    movements.pop();
+                    pins
+                }
+                _ => [false; 4],
+            };
+            pins[pin] = true;
+            movements.push(ClockMovement::SetPins(ClockPins::from_array(pins)));
+            continue;
+        }
+
+        let name_end = token
+            .find(|c: char| !c.is_ascii_uppercase())
+            .ok_or_else(|| ClockParseError::InvalidToken(token.into()))?;
+        let pins = ClockPins::from_name(&token[..name_end])
+            .ok_or_else(|| ClockParseError::InvalidToken(token.into()))?;
+        let rest = &token[name_end..];
+        let (amount, sign) = match rest.char_indices().last() {
+            Some((i, '+')) => (&rest[..i], 1),
+            Some((i, '-')) => (&rest[..i], -1),
+            _ => return Err(ClockParseError::InvalidToken(token.into())),
+        };
+        let amount = match amount.parse::<i8>() {
+            Ok(amount) if (0..=6).contains(&amount) => amount,
+            Ok(_) => return Err(ClockParseError::InvalidAmount(token.into())),
+            Err(_) => return Err(ClockParseError::InvalidToken(token.into())),
+        };
+        movements.push(ClockMovement::Turn {
+            pins,
+            amount: amount * sign,
+        });
+    }
+    Ok(movements)
+}
+
+/// クロックの状態を表す。
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Clock {
+    /// 各ダイヤルの時刻 (0 から 11、0 が 12 時)。
+    /// `ClockFace::ALL` の順に、それぞれの面を外側から見た左上から行ごとに並べる
+    dials: [u8; DIALS_PER_FACE * 2],
+
+    /// `ClockFace::Front` 側から見たピンの状態
+    pins: ClockPins,
+
+    /// `ClockFace::Back` を手前に向けているかどうか
+    flipped: bool,
+}
+
+impl Default for Clock {
+    fn default() -> Clock {
+        Clock::new()
+    }
+}
+
+impl Clock {
+    /// 全てのダイヤルが 12 時を指し、ピンが全て下がった状態のクロックを生成する。
+    pub fn new() -> Clock {
+        Clock {
+            dials: [0; DIALS_PER_FACE * 2],
+            pins: ClockPins::default(),
+            flipped: false,
+        }
+    }
+
+    /// 面を外側から見た左上から行ごとに並べたダイヤルの時刻を返す。
+    pub fn dials(&self, face: ClockFace) -> &[u8] {
+        let offset = face_offset(face);
+        &self.dials[offset..offset + DIALS_PER_FACE]
+    }
+
+    /// 指定した面から見たピンの状態を返す。
+    pub fn pins(&self, face: ClockFace) -> ClockPins {
+        match face {
+            ClockFace::Front => self.pins,
+            ClockFace::Back => self.pins.reversed(),
+        }
+    }
+
+    /// 手前に向けている面を返す。
+    pub fn facing(&self) -> ClockFace {
+        if self.flipped {
+            ClockFace::Back
+        } else {
+            ClockFace::Front
+        }
+    }
+
+    /// 操作を適用する。
+    pub fn apply(&mut self, movement: ClockMovement) {
+        let (near, far) = if self.flipped {
+            (ClockFace::Back, ClockFace::Front)
+        } else {
+            (ClockFace::Front, ClockFace::Back)
+        };
+
+        match movement {
+            ClockMovement::Turn { pins, amount } => {
+                self.set_pins(near, pins);
+                let amount = amount.rem_euclid(12) as u8;
+                let mut turned = [false; DIALS_PER_FACE];
+                for (i, &up) in pins.to_array().iter().enumerate() {
+                    if !up {
+                        continue;
+                    }
+                    for &dial in &PIN_DIALS[i] {
+                        turned[dial] = true;
+                    }
+
+                    // 角のダイヤルは裏側の左右反対の位置のダイヤルと連動する
+                    let mirrored = CORNER_DIALS[i ^ 1];
+                    let dial = &mut self.dials[face_offset(far) + mirrored];
+                    *dial = (*dial + 12 - amount) % 12;
+                }
+                for (i, _) in turned.iter().enumerate().filter(|(_, &t)| t) {
+                    let dial = &mut self.dials[face_offset(near) + i];
+                    *dial = (*dial + amount) % 12;
+                }
+            }
+            ClockMovement::Flip => self.flipped = !self.flipped,
+            ClockMovement::SetPins(pins) => self.set_pins(near, pins),
+        }
+    }
+
+    /// 指定した面から見たピンの状態を設定する。
+    fn set_pins(&mut self, face: ClockFace, pins: ClockPins) {
+        self.pins = match face {
+            ClockFace::Front => pins,
+            ClockFace::Back => pins.reversed(),
+        };
+    }
+}
+
+impl Puzzle for Clock {
+    type Movement = ClockMovement;
+    type Face = ClockFace;
+    type ParseError = ClockParseError;
+    type Error = Infallible;
+
+    const FACES: &'static [ClockFace] = &ClockFace::ALL;
+
+    fn parse_movements(source: &str) -> Result<Vec<ClockMovement>, ClockParseError> {
+        parse_clock_movements(source)
+    }
+
+    fn inverse_movement(movement: ClockMovement) -> ClockMovement {
+        movement.inverse()
+    }
+
+    fn apply_movement(&mut self, movement: ClockMovement) -> Result<(), Infallible> {
+        self.apply(movement);
+        Ok(())
+    }
+
+    /// クロックにはステッカーがないため、常に空を返す。
+    fn stickers(&self, _face: ClockFace) -> &[Sticker<ClockFace>] {
+        &[]
+    }
+
+    /// 全てのダイヤルが 12 時を指していれば揃っているとみなす。
+    fn is_solved(&self) -> bool {
+        self.dials.iter().all(|&d| d == 0)
+    }
+}
+
+/// 面のダイヤルの先頭のインデックスを返す。
+fn face_offset(face: ClockFace) -> usize {
+    match face {
+        ClockFace::Front => 0,
+        ClockFace::Back => DIALS_PER_FACE,
+    }
+}
@@ -0,0 +1,292 @@
+use super::{
+    geometry::{centroid, normalize, permute, triangle_centroids, twist_targets, Point},
+    Puzzle,
+};
+use crate::{
+    cube::Sticker,
+    notation::{MovementParseError, Rotation},
+};
+use std::{
+    convert::Infallible,
+    f64::consts::{FRAC_1_SQRT_2, PI},
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+/// 1 つの面のステッカーの数。
+const STICKERS_PER_FACE: usize = 9;
+
+/// FTO の頂点の座標。正面と背面の頂点を z 軸上に、残りの 4 頂点を正面から見て斜め方向に置く。
+const FRONT: Point = [0.0, 0.0, 1.0];
+const BACK: Point = [0.0, 0.0, -1.0];
+const UP_LEFT: Point = [-FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0];
+const UP_RIGHT: Point = [FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0];
+const DOWN_LEFT: Point = [-FRAC_1_SQRT_2, -FRAC_1_SQRT_2, 0.0];
+const DOWN_RIGHT: Point = [FRAC_1_SQRT_2, -FRAC_1_SQRT_2, 0.0];
+
+/// FTO の面を表す。
+/// 正面の頂点の周りに U, R, F, L 面、背面の頂点の周りにそれぞれの対面が並ぶ。
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FtoFace {
+    /// U 面
+    Up,
+
+    /// F 面
+    Front,
+
+    /// R 面
+    Right,
+
+    /// L 面
+    Left,
+
+    /// B 面 (F 面の対面)
+    Back,
+
+    /// D 面 (U 面の対面)
+    Down,
+
+    /// BR 面 (L 面の対面)
+    BackRight,
+
+    /// BL 面 (R 面の対面)
+    BackLeft,
+}
+
+impl FtoFace {
+    /// 全ての面。
+    pub const ALL: [FtoFace; 8] = [
+        FtoFace::Up,
+        FtoFace::Front,
+        FtoFace::Right,
+        FtoFace::Left,
+        FtoFace::Back,
+        FtoFace::Down,
+        FtoFace::BackRight,
+        FtoFace::BackLeft,
+    ];
+
+    /// 面の表記を返す。
+    fn name(self) -> &'static str {
+        match self {
+            FtoFace::Up => "U",
+            FtoFace::Front => "F",
+            FtoFace::Right => "R",
+            FtoFace::Left => "L",
+            FtoFace::Back => "B",
+            FtoFace::Down => "D",
+            FtoFace::BackRight => "BR",
+            FtoFace::BackLeft => "BL",
+        }
+    }
+
+    /// 面を構成する頂点を、外側から見て反時計回りに返す。
+    /// 先頭は正面または背面の頂点になる。
+    pub(crate) fn vertices(self) -> [Point; 3] {
+        match self {
+            FtoFace::Up => [FRONT, UP_RIGHT, UP_LEFT],
+            FtoFace::Front => [FRONT, DOWN_LEFT, DOWN_RIGHT],
+            FtoFace::Right => [FRONT, DOWN_RIGHT, UP_RIGHT],
+            FtoFace::Left => [FRONT, UP_LEFT, DOWN_LEFT],
+            FtoFace::Back => [BACK, UP_LEFT, UP_RIGHT],
+            FtoFace::Down => [BACK, DOWN_RIGHT, DOWN_LEFT],
+            FtoFace::BackRight => [BACK, UP_RIGHT, DOWN_RIGHT],
+            FtoFace::BackLeft => [BACK, DOWN_LEFT, UP_LEFT],
+        }
+    }
+
+    /// 面の外向きの単位法線ベクトルを返す。
+    fn normal(self) -> Point {
+        normalize(centroid(&self.vertices()))
+    }
+}
+
+impl Display for FtoFace {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// FTO の 1 つの操作を表す。
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct FtoMovement {
+    /// 回転する面
+    pub face: FtoFace,
+
+    /// `[U]` のように面の周りにパズル全体を回転するかどうか
+    pub rotation: bool,
+
+    /// 面の外側から見た回転方向。
+    pub direction: Rotation,
+}
+
+impl FtoMovement {
+    /// 逆操作を返す。
+    pub fn inverse(&self) -> FtoMovement {
+        FtoMovement {
+            direction: match self.direction {
+                Rotation::Clockwise => Rotation::Counterclockwise,
+                Rotation::Counterclockwise | Rotation::Turnover => Rotation::Clockwise,
+            },
+            ..*self
+        }
+    }
+}
+
+impl Display for FtoMovement {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let suffix = match self.direction {
+            Rotation::Clockwise => "",
+            Rotation::Counterclockwise => "'",
+            Rotation::Turnover => "2",
+        };
+        if self.rotation {
+            write!(f, "[{}{}]", self.face, suffix)
+        } else {
+            write!(f, "{}{}", self.face, suffix)
+        }
+    }
+}
+
+/// FTO の回転記号をパースする。
+/// 空白で区切られた `BR'` のような面の回転と、`[U]` のような持ち替えを受け付ける。
+pub fn parse_fto_movements(source: &str) -> Result<Vec<FtoMovement>, MovementParseError> {
+    source.split_whitespace().map(parse_token).collect()
+}
+
+/// 1 つの回転記号をパースする。
+fn parse_token(token: &str) -> Result<FtoMovement, MovementParseError> {
+    let (body, rotation) = match token.strip_prefix('[') {
+        Some(inner) => match inner.strip_suffix(']') {
+            Some(body) => (body, true),
+            None => return Err(MovementParseError::InvalidFace('[')),
+        },
+        None => (token, false),
+    };
+
+    // 長い表記から順に照合する
+    let face = FtoFace::ALL
+        .iter()
+        .filter(|f| body.starts_with(f.name()))
+        .max_by_key(|f| f.name().len())
+        .copied();
+    let face = match face {
+        Some(face) => face,
+        None => {
+            let c = body.chars().next().unwrap_or(']');
+            return Err(MovementParseError::InvalidFace(c));
+        }
+    };
+
+    let direction = match &body[face.name().len()..] {
+        "" => Rotation::Clockwise,
+        "'" => Rotation::Counterclockwise,
+        "2" => Rotation::Turnover,
+        suffix => {
+            let c = suffix.chars().next().expect("Suffix should not be empty");
+            return Err(MovementParseError::InvalidSuffix(c));
+        }
+    };
+    Ok(FtoMovement {
+        face,
+        rotation,
+        direction,
+    })
+}
+
+/// FTO の状態を表す。
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Fto {
+    /// `FtoFace::ALL` の順に、各面のステッカーを `TRIANGLES` の順で並べたもの
+    stickers: Vec<Sticker<FtoFace>>,
+}
+
+impl Default for Fto {
+    fn default() -> Fto {
+        Fto::new()
+    }
+}
+
+impl Fto {
+    /// 揃った状態の FTO を生成する。
+    pub fn new() -> Fto {
+        let stickers = FtoFace::ALL
+            .iter()
+            .flat_map(|&face| vec![Sticker::Face(face); STICKERS_PER_FACE])
+            .collect();
+        Fto { stickers }
+    }
+
+    /// 指定した位置のステッカーを返す。
+    pub fn sticker(&self, face: FtoFace, index: usize) -> Option<&Sticker<FtoFace>> {
+        if index >= STICKERS_PER_FACE {
+            return None;
+        }
+        Some(&self.stickers[face_offset(face) + index])
+    }
+
+    /// 操作を適用する。
+    pub fn apply(&mut self, movement: FtoMovement) {
+        let count = match movement.direction {
+            Rotation::Clockwise => 1.0,
+            Rotation::Counterclockwise | Rotation::Turnover => 2.0,
+        };
+
+        // 面から対面までを 3 等分した層のうち、面の回転は外側の 1 層を回す
+        let depth = if movement.rotation {
+            -1.0
+        } else {
+            1.0 / (3.0 * 3.0f64.sqrt())
+        };
+        let targets = twist_targets(
+            &centroids(),
+            movement.face.normal(),
+            depth,
+            -2.0 * PI / 3.0 * count,
+        );
+        permute(&mut self.stickers, &targets);
+    }
+}
+
+impl Puzzle for Fto {
+    type Movement = FtoMovement;
+    type Face = FtoFace;
+    type ParseError = MovementParseError;
+    type Error = Infallible;
+
+    const FACES: &'static [FtoFace] = &FtoFace::ALL;
+
+    fn parse_movements(source: &str) -> Result<Vec<FtoMovement>, MovementParseError> {
+        parse_fto_movements(source)
+    }
+
+    fn inverse_movement(movement: FtoMovement) -> FtoMovement {
+        movement.inverse()
+    }
+
+    fn apply_movement(&mut self, movement: FtoMovement) -> Result<(), Infallible> {
+        self.apply(movement);
+        Ok(())
+    }
+
+    fn stickers(&self, face: FtoFace) -> &[Sticker<FtoFace>] {
+        let offset = face_offset(face);
+        &self.stickers[offset..offset + STICKERS_PER_FACE]
+    }
+}
+
+/// 面のステッカーの先頭のインデックスを返す。
+fn face_offset(face: FtoFace) -> usize {
+    let index = FtoFace::ALL
+        .iter()
+        .position(|&f| f == face)
+        .expect("Every face should be listed");
+    index * STICKERS_PER_FACE
+}
+
+/// 全てのステッカーの重心を求める。
+fn centroids() -> Vec<Point> {
+    FtoFace::ALL
+        .iter()
+        .flat_map(|face| triangle_centroids(face.vertices()))
+        .collect()
+}
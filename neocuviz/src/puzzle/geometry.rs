@@ -0,0 +1,120 @@
+//! ステッカーの重心の回転から置換を求める、非立方体パズル共通のヘルパー
+
+/// 3 次元の点またはベクトル。
+pub(crate) type Point = [f64; 3];
+
+/// 重心が一致しているとみなす距離。
+const EPSILON: f64 = 1e-6;
+
+/// 面の三角形の分割。
+/// 面の 3 頂点 (v0, v1, v2) に対して、v0 から v1 方向に i/3、v2 方向に j/3 進んだ格子点 (i, j) で表す。
+/// v0 側の頂点から行ごとに並べる。
+pub(crate) const TRIANGLES: [[(usize, usize); 3]; 9] = [
+    [(0, 0), (1, 0), (0, 1)],
+    [(1, 0), (2, 0), (1, 1)],
+    [(1, 0), (1, 1), (0, 1)],
+    [(0, 1), (1, 1), (0, 2)],
+    [(2, 0), (3, 0), (2, 1)],
+    [(2, 0), (2, 1), (1, 1)],
+    [(1, 1), (2, 1), (1, 2)],
+    [(1, 1), (1, 2), (0, 2)],
+    [(0, 2), (1, 2), (0, 3)],
+];
+
+/// 内積を求める。
+pub(crate) fn dot(a: Point, b: Point) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// 正規化したベクトルを返す。
+pub(crate) fn normalize(v: Point) -> Point {
+    let length = dot(v, v).sqrt();
+    [v[0] / length, v[1] / length, v[2] / length]
+}
+
+/// 複数の点の重心を求める。
+pub(crate) fn centroid(points: &[Point]) -> Point {
+    let mut sum = [0.0; 3];
+    for point in points {
+        for (s, p) in sum.iter_mut().zip(point.iter()) {
+            *s += p;
+        }
+    }
+    let count = points.len() as f64;
+    [sum[0] / count, sum[1] / count, sum[2] / count]
+}
+
+/// 3 頂点 `vertices` の三角形を `TRIANGLES` で分割し、各三角形の重心を求める。
+pub(crate) fn triangle_centroids(vertices: [Point; 3]) -> Vec<Point> {
+    let [v0, v1, v2] = vertices;
+    TRIANGLES
+        .iter()
+        .map(|triangle| {
+            let corners: Vec<_> = triangle
+                .iter()
+                .map(|&(i, j)| {
+                    let (s, t) = (i as f64 / 3.0, j as f64 / 3.0);
+                    let mut point = [0.0; 3];
+                    for (k, p) in point.iter_mut().enumerate() {
+                        *p = v0[k] + (v1[k] - v0[k]) * s + (v2[k] - v0[k]) * t;
+                    }
+                    point
+                })
+                .collect();
+            centroid(&corners)
+        })
+        .collect()
+}
+
+/// 単位ベクトル `axis` の周りに `angle` だけ回転する。
+/// 軸の先から見て反時計回りが正の向きになる。
+pub(crate) fn rotate(point: Point, axis: Point, angle: f64) -> Point {
+    let (sin, cos) = angle.sin_cos();
+    let cross = [
+        axis[1] * point[2] - axis[2] * point[1],
+        axis[2] * point[0] - axis[0] * point[2],
+        axis[0] * point[1] - axis[1] * point[0],
+    ];
+    let projection = dot(axis, point) * (1.0 - cos);
+    let mut rotated = [0.0; 3];
+    for (i, r) in rotated.iter_mut().enumerate() {
+        *r = point[i] * cos + cross[i] * sin + axis[i] * projection;
+    }
+    rotated
+}
+
+/// `axis` 方向の成分が `depth` より大きいステッカーを回転した時に、
+/// 各ステッカーが移動する先のインデックスを求める。
+pub(crate) fn twist_targets(
+    centroids: &[Point],
+    axis: Point,
+    depth: f64,
+    angle: f64,
+) -> Vec<usize> {
+    let axis = normalize(axis);
+    centroids
+        .iter()
+        .enumerate()
+        .map(|(i, &point)| {
+            if dot(point, axis) <= depth {
+                return i;
+            }
+            let rotated = rotate(point, axis, angle);
+            centroids
+                .iter()
+                .position(|&c| {
+                    let diff = [c[0] - rotated[0], c[1] - rotated[1], c[2] - rotated[2]];
+                    dot(diff, diff) < EPSILON
+                })
+                .expect("Rotated sticker should match another sticker")
+        })
+        .collect()
+}
+
+/// `targets` に従って要素を並べ替える。
+pub(crate) fn permute<T: Clone>(items: &mut [T], targets: &[usize]) {
+    let original = items.to_vec();
+    for (item, &target) in original.into_iter().zip(targets.iter()) {
+        items[target] = item;
+    }
+}
@@ -0,0 +1,428 @@
+use super::{
+    geometry::{dot, normalize, permute, twist_targets, Point},
+    Puzzle,
+};
+use crate::{cube::Sticker, notation::MovementParseError};
+use std::{
+    convert::Infallible,
+    f64::consts::PI,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+/// 1 つの面のステッカーの数。
+const STICKERS_PER_FACE: usize = 11;
+
+/// 角のステッカーの 1 辺が面の辺に占める割合。
+const CORNER_RATIO: f64 = 0.35;
+
+/// 面の回転で動くステッカーの重心の、回転軸方向の成分の下限。
+/// 内接球の半径を 1 とした時、隣接面の辺のステッカーと回転する層のステッカーの間の値になる。
+const LAYER_DEPTH: f64 = 0.7;
+
+/// メガミンクスの面を表す。
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MegaminxFace {
+    /// U 面
+    Up,
+
+    /// F 面
+    Front,
+
+    /// R 面
+    Right,
+
+    /// BR 面
+    BackRight,
+
+    /// BL 面
+    BackLeft,
+
+    /// L 面
+    Left,
+
+    /// DR 面 (BL 面の対面)
+    DownRight,
+
+    /// DBR 面 (L 面の対面)
+    DownBackRight,
+
+    /// B 面 (F 面の対面)
+    Back,
+
+    /// DBL 面 (R 面の対面)
+    DownBackLeft,
+
+    /// DL 面 (BR 面の対面)
+    DownLeft,
+
+    /// D 面
+    Down,
+}
+
+impl MegaminxFace {
+    /// 全ての面。
+    pub const ALL: [MegaminxFace; 12] = [
+        MegaminxFace::Up,
+        MegaminxFace::Front,
+        MegaminxFace::Right,
+        MegaminxFace::BackRight,
+        MegaminxFace::BackLeft,
+        MegaminxFace::Left,
+        MegaminxFace::DownRight,
+        MegaminxFace::DownBackRight,
+        MegaminxFace::Back,
+        MegaminxFace::DownBackLeft,
+        MegaminxFace::DownLeft,
+        MegaminxFace::Down,
+    ];
+
+    /// 面の表記を返す。
+    fn name(self) -> &'static str {
+        match self {
+            MegaminxFace::Up => "U",
+            MegaminxFace::Front => "F",
+            MegaminxFace::Right => "R",
+            MegaminxFace::BackRight => "BR",
+            MegaminxFace::BackLeft => "BL",
+            MegaminxFace::Left => "L",
+            MegaminxFace::DownRight => "DR",
+            MegaminxFace::DownBackRight => "DBR",
+            MegaminxFace::Back => "B",
+            MegaminxFace::DownBackLeft => "DBL",
+            MegaminxFace::DownLeft => "DL",
+            MegaminxFace::Down => "D",
+        }
+    }
+
+    /// 面の外向きの単位法線ベクトルを返す。
+    /// U 面を +y、F 面を +z 寄りに置き、上下の 5 面は y 軸の周りに 72° ずつ並ぶ。
+    fn normal(self) -> Point {
+        let (upper, azimuth) = match self {
+            MegaminxFace::Up => return [0.0, 1.0, 0.0],
+            MegaminxFace::Down => return [0.0, -1.0, 0.0],
+            MegaminxFace::Front => (true, 0.0),
+            MegaminxFace::Right => (true, 1.0),
+            MegaminxFace::BackRight => (true, 2.0),
+            MegaminxFace::BackLeft => (true, 3.0),
+            MegaminxFace::Left => (true, 4.0),
+            MegaminxFace::DownRight => (false, 0.5),
+            MegaminxFace::DownBackRight => (false, 1.5),
+            MegaminxFace::Back => (false, 2.5),
+            MegaminxFace::DownBackLeft => (false, 3.5),
+            MegaminxFace::DownLeft => (false, 4.5),
+        };
+        let elevation = if upper { 0.5f64.atan() } else { -0.5f64.atan() };
+        let azimuth = azimuth * PI * 2.0 / 5.0;
+        [
+            elevation.cos() * azimuth.sin(),
+            elevation.sin(),
+            elevation.cos() * azimuth.cos(),
+        ]
+    }
+
+    /// 面の頂点を外側から見て反時計回りに返す。内接球の半径を 1 とする。
+    pub(crate) fn vertices(self) -> [Point; 5] {
+        let normal = self.normal();
+        let adjacent_dot = 1.0 / 5.0f64.sqrt();
+        let mut neighbors: Vec<Point> = MegaminxFace::ALL
+            .iter()
+            .map(|f| f.normal())
+            .filter(|&n| (dot(n, normal) - adjacent_dot).abs() < 1e-6)
+            .collect();
+
+        // 面内の基底を取って隣接面を反時計回りに並べる
+        let first = neighbors[0];
+        let base = normalize(sub(first, scale(normal, dot(first, normal))));
+        let cross = [
+            normal[1] * base[2] - normal[2] * base[1],
+            normal[2] * base[0] - normal[0] * base[2],
+            normal[0] * base[1] - normal[1] * base[0],
+        ];
+        let angle = |n: &Point| {
+            let a = dot(*n, cross).atan2(dot(*n, base));
+            if a < 0.0 {
+                a + PI * 2.0
+            } else {
+                a
+            }
+        };
+        neighbors.sort_by(|a, b| {
+            angle(a)
+                .partial_cmp(&angle(b))
+                .expect("Angle should be finite")
+        });
+
+        // 頂点は 3 面の法線の和の方向にある
+        let factor = 1.0 / (1.0 + 2.0 * adjacent_dot);
+        let mut vertices = [[0.0; 3]; 5];
+        for (i, vertex) in vertices.iter_mut().enumerate() {
+            let (a, b) = (neighbors[i], neighbors[(i + 1) % 5]);
+            for (k, v) in vertex.iter_mut().enumerate() {
+                *v = (normal[k] + a[k] + b[k]) * factor;
+            }
+        }
+        vertices
+    }
+}
+
+impl Display for MegaminxFace {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// 面のステッカーの形状を、面の 5 頂点に対する重みの組で表したものを返す。
+/// 中央の五角形を先頭に、頂点 i の角、頂点 i と i + 1 の間の辺の順に並べる。
+pub(crate) fn sticker_weights() -> Vec<Vec<[f64; 5]>> {
+    // 頂点 i から頂点 j 方向に CORNER_RATIO だけ進んだ点
+    let toward = |i: usize, j: usize| {
+        let mut w = [0.0; 5];
+        w[i] += 1.0 - CORNER_RATIO;
+        w[j] += CORNER_RATIO;
+        w
+    };
+    // 頂点 i の角のステッカーの内側の頂点
+    let inner = |i: usize| {
+        let mut w = [0.0; 5];
+        w[i] += 1.0 - CORNER_RATIO * 2.0;
+        w[(i + 1) % 5] += CORNER_RATIO;
+        w[(i + 4) % 5] += CORNER_RATIO;
+        w
+    };
+    let vertex = |i: usize| {
+        let mut w = [0.0; 5];
+        w[i] = 1.0;
+        w
+    };
+
+    let mut shapes = vec![(0..5).map(inner).collect()];
+    for i in 0..5 {
+        let next = (i + 1) % 5;
+        shapes.push(vec![
+            vertex(i),
+            toward(i, next),
+            inner(i),
+            toward(i, (i + 4) % 5),
+        ]);
+        shapes.push(vec![
+            toward(i, next),
+            toward(next, i),
+            inner(next),
+            inner(i),
+        ]);
+    }
+    shapes
+}
+
+/// メガミンクスの 1 つの操作を表す。
+/// 回転量は 1/5 回転を単位とし、面の外側から見て時計回りを正とする。
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum MegaminxMovement {
+    /// 1 つの面の回転
+    Face { face: MegaminxFace, amount: i8 },
+
+    /// Pochmann 記法の回転。`face` の対面の層を除いたパズル全体を回転する。
+    /// `R++` は DBR 面、`D++` は D 面の周りの回転になる
+    Pochmann { face: MegaminxFace, amount: i8 },
+}
+
+impl MegaminxMovement {
+    /// 逆操作を返す。
+    pub fn inverse(&self) -> MegaminxMovement {
+        match *self {
+            MegaminxMovement::Face { face, amount } => MegaminxMovement::Face {
+                face,
+                amount: -amount,
+            },
+            MegaminxMovement::Pochmann { face, amount } => MegaminxMovement::Pochmann {
+                face,
+                amount: -amount,
+            },
+        }
+    }
+}
+
+impl Display for MegaminxMovement {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match *self {
+            MegaminxMovement::Face { face, amount } => {
+                write!(f, "{}", face)?;
+                if amount.abs() >= 2 {
+                    write!(f, "{}", amount.abs())?;
+                }
+                if amount < 0 {
+                    write!(f, "'")?;
+                }
+                Ok(())
+            }
+            MegaminxMovement::Pochmann { face, amount } => {
+                let name = match face {
+                    MegaminxFace::DownBackRight => "R",
+                    _ => face.name(),
+                };
+                let sign = if amount < 0 { "-" } else { "+" };
+                write!(f, "{}{}", name, sign.repeat(amount.unsigned_abs() as usize))
+            }
+        }
+    }
+}
+
+/// メガミンクスの回転記号をパースする。
+/// 空白で区切られた `R2'` のような面の回転と、Pochmann 記法の `R++` `D--` を受け付ける。
+pub fn parse_megaminx_movements(source: &str) -> Result<Vec<MegaminxMovement>, MovementParseError> {
+    source.split_whitespace().map(parse_token).collect()
+}
+
+/// 1 つの回転記号をパースする。
+fn parse_token(token: &str) -> Result<MegaminxMovement, MovementParseError> {
+    // 長い表記から順に照合する
+    let face = MegaminxFace::ALL
+        .iter()
+        .filter(|f| token.starts_with(f.name()))
+        .max_by_key(|f| f.name().len())
+        .copied();
+    let face = match face {
+        Some(face) => face,
+        None => {
+            let c = token.chars().next().expect("Token should not be empty");
+            return Err(MovementParseError::InvalidFace(c));
+        }
+    };
+
+    let suffix = &token[face.name().len()..];
+    let movement = match (face, suffix) {
+        (_, "") => MegaminxMovement::Face { face, amount: 1 },
+        (_, "'") => MegaminxMovement::Face { face, amount: -1 },
+        (_, "2") => MegaminxMovement::Face { face, amount: 2 },
+        (_, "2'") => MegaminxMovement::Face { face, amount: -2 },
+        (MegaminxFace::Right, "++") => MegaminxMovement::Pochmann {
+            face: MegaminxFace::DownBackRight,
+            amount: 2,
+        },
+        (MegaminxFace::Right, "--") => MegaminxMovement::Pochmann {
+            face: MegaminxFace::DownBackRight,
+            amount: -2,
+        },
+        (MegaminxFace::Down, "++") => MegaminxMovement::Pochmann { face, amount: 2 },
+        (MegaminxFace::Down, "--") => MegaminxMovement::Pochmann { face, amount: -2 },
+        _ => {
+            let c = suffix.chars().next().expect("Suffix should not be empty");
+            return Err(MovementParseError::InvalidSuffix(c));
+        }
+    };
+    Ok(movement)
+}
+
+/// メガミンクスの状態を表す。
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Megaminx {
+    /// `MegaminxFace::ALL` の順に、各面のステッカーを `sticker_weights` の順で並べたもの
+    stickers: Vec<Sticker<MegaminxFace>>,
+}
+
+impl Default for Megaminx {
+    fn default() -> Megaminx {
+        Megaminx::new()
+    }
+}
+
+impl Megaminx {
+    /// 揃った状態のメガミンクスを生成する。
+    pub fn new() -> Megaminx {
+        let stickers = MegaminxFace::ALL
+            .iter()
+            .flat_map(|&face| vec![Sticker::Face(face); STICKERS_PER_FACE])
+            .collect();
+        Megaminx { stickers }
+    }
+
+    /// 指定した位置のステッカーを返す。
+    pub fn sticker(&self, face: MegaminxFace, index: usize) -> Option<&Sticker<MegaminxFace>> {
+        if index >= STICKERS_PER_FACE {
+            return None;
+        }
+        Some(&self.stickers[face_offset(face) + index])
+    }
+
+    /// 操作を適用する。
+    pub fn apply(&mut self, movement: MegaminxMovement) {
+        let (face, amount, depth) = match movement {
+            MegaminxMovement::Face { face, amount } => (face, amount, LAYER_DEPTH),
+            MegaminxMovement::Pochmann { face, amount } => (face, amount, -LAYER_DEPTH),
+        };
+        let amount = amount.rem_euclid(5);
+        if amount == 0 {
+            return;
+        }
+        let angle = -2.0 * PI / 5.0 * amount as f64;
+        let targets = twist_targets(&centroids(), face.normal(), depth, angle);
+        permute(&mut self.stickers, &targets);
+    }
+}
+
+impl Puzzle for Megaminx {
+    type Movement = MegaminxMovement;
+    type Face = MegaminxFace;
+    type ParseError = MovementParseError;
+    type Error = Infallible;
+
+    const FACES: &'static [MegaminxFace] = &MegaminxFace::ALL;
+
+    fn parse_movements(source: &str) -> Result<Vec<MegaminxMovement>, MovementParseError> {
+        parse_megaminx_movements(source)
+    }
+
+    fn inverse_movement(movement: MegaminxMovement) -> MegaminxMovement {
+        movement.inverse()
+    }
+
+    fn apply_movement(&mut self, movement: MegaminxMovement) -> Result<(), Infallible> {
+        self.apply(movement);
+        Ok(())
+    }
+
+    fn stickers(&self, face: MegaminxFace) -> &[Sticker<MegaminxFace>] {
+        let offset = face_offset(face);
+        &self.stickers[offset..offset + STICKERS_PER_FACE]
+    }
+}
+
+/// 面のステッカーの先頭のインデックスを返す。
+fn face_offset(face: MegaminxFace) -> usize {
+    let index = MegaminxFace::ALL
+        .iter()
+        .position(|&f| f == face)
+        .expect("Every face should be listed");
+    index * STICKERS_PER_FACE
+}
+
+/// 全てのステッカーの重心を求める。
+fn centroids() -> Vec<Point> {
+    let shapes = sticker_weights();
+    let mut points = Vec::with_capacity(MegaminxFace::ALL.len() * STICKERS_PER_FACE);
+    for face in &MegaminxFace::ALL {
+        let vertices = face.vertices();
+        for shape in &shapes {
+            let mut point = [0.0; 3];
+            for weights in shape {
+                for (vertex, &w) in vertices.iter().zip(weights.iter()) {
+                    for (p, v) in point.iter_mut().zip(vertex.iter()) {
+                        *p += v * w / shape.len() as f64;
+                    }
+                }
+            }
+            points.push(point);
+        }
+    }
+    points
+}
+
+/// ベクトルの差を求める。
+fn sub(a: Point, b: Point) -> Point {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+/// ベクトルを定数倍する。
+fn scale(v: Point, k: f64) -> Point {
+    [v[0] * k, v[1] * k, v[2] * k]
+}
@@ -0,0 +1,99 @@
+mod clock;
+mod fto;
+mod geometry;
+mod megaminx;
+mod pyraminx;
+mod skewb;
+mod square1;
+
+pub use clock::{
+    parse_clock_movements, Clock, ClockFace, ClockMovement, ClockParseError, ClockPins,
+};
+pub use fto::{parse_fto_movements, Fto, FtoFace, FtoMovement};
+pub(crate) use geometry::TRIANGLES;
+pub(crate) use megaminx::sticker_weights as megaminx_sticker_weights;
+pub use megaminx::{parse_megaminx_movements, Megaminx, MegaminxFace, MegaminxMovement};
+pub use pyraminx::{
+    parse_pyraminx_movements, Pyraminx, PyraminxFace, PyraminxMovement, PyraminxVertex,
+};
+pub(crate) use skewb::SKEWB_STICKERS;
+pub use skewb::{parse_skewb_movements, Skewb, SkewbCorner, SkewbMovement};
+pub use square1::{
+    parse_square1_movements, Square1, Square1Error, Square1Movement, Square1ParseError, Square1Part,
+};
+
+use crate::{
+    cube::Sticker,
+    notation::{MovementParseError, Rotation},
+};
+use std::{
+    error::Error,
+    fmt::{Debug, Display},
+    hash::Hash,
+};
+
+/// 手順の適用や描画の対象となるパズルが実装するトレイト。
+pub trait Puzzle: Clone {
+    /// 1 つの操作
+    type Movement: Debug + Display + Clone + Copy + PartialEq;
+
+    /// ステッカーが貼られる面
+    type Face: Debug + Clone + Copy + PartialEq + Eq + Hash + 'static;
+
+    /// 回転記号のパースエラー
+    type ParseError: Error + Send + Sync + 'static;
+
+    /// 操作を適用する時のエラー
+    type Error: Error + Send + Sync + 'static;
+
+    /// 全ての面。
+    const FACES: &'static [Self::Face];
+
+    /// 回転記号列をパースする。
+    fn parse_movements(source: &str) -> Result<Vec<Self::Movement>, Self::ParseError>;
+
+    /// 逆操作を返す。
+    fn inverse_movement(movement: Self::Movement) -> Self::Movement;
+
+    /// 操作を適用する。
+    fn apply_movement(&mut self, movement: Self::Movement) -> Result<(), Self::Error>;
+
+    /// 面に貼られているステッカーを返す。
+    /// 並び順はパズルごとに定める。
+    fn stickers(&self, face: Self::Face) -> &[Sticker<Self::Face>];
+
+    /// 揃っているかどうかを返す。
+    /// 既定では全ての面でステッカーの色が揃っているかどうかで判定する。
+    fn is_solved(&self) -> bool {
+        Self::FACES.iter().all(|&face| {
+            let stickers = self.stickers(face);
+            stickers.iter().all(|s| Some(s) == stickers.first())
+        })
+    }
+}
+
+/// 1 文字の記号と省略可能な回転方向 (`'` または `2`) からなる回転記号列をパースする。
+/// `target` は記号から回転の対象を求め、不正な記号では `None` を返す。
+pub(crate) fn parse_letter_movements<T>(
+    source: &str,
+    mut target: impl FnMut(char) -> Option<T>,
+) -> Result<Vec<(T, Rotation)>, MovementParseError> {
+    let mut movements = vec![];
+    let mut chars = source.chars().filter(|c| !c.is_whitespace()).peekable();
+    while let Some(c) = chars.next() {
+        let target = target(c).ok_or(MovementParseError::InvalidFace(c))?;
+        let direction = match chars.peek() {
+            Some('\'') => {
+                chars.next();
+                Rotation::Counterclockwise
+            }
+            Some('2') => {
+                chars.next();
+                Rotation::Turnover
+            }
+            _ => Rotation::Clockwise,
+        };
+        movements.push((target, direction));
+    }
+    Ok(movements)
+}
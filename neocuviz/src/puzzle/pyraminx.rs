@@ -0,0 +1,262 @@
+use super::{
+    geometry::{permute, triangle_centroids, twist_targets, Point},
+    parse_letter_movements, Puzzle,
+};
+use crate::{
+    cube::Sticker,
+    notation::{MovementParseError, Rotation},
+};
+use std::{
+    convert::Infallible,
+    f64::consts::PI,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+/// 1 つの面のステッカーの数。
+const STICKERS_PER_FACE: usize = 9;
+
+/// ピラミンクスの頂点を表す。
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PyraminxVertex {
+    /// U 頂点
+    Up,
+
+    /// L 頂点
+    Left,
+
+    /// R 頂点
+    Right,
+
+    /// B 頂点
+    Back,
+}
+
+impl PyraminxVertex {
+    /// 頂点の座標を返す。
+    fn position(self) -> Point {
+        match self {
+            PyraminxVertex::Up => [1.0, 1.0, 1.0],
+            PyraminxVertex::Left => [-1.0, -1.0, 1.0],
+            PyraminxVertex::Right => [1.0, -1.0, -1.0],
+            PyraminxVertex::Back => [-1.0, 1.0, -1.0],
+        }
+    }
+}
+
+/// ピラミンクスの面を表す。
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PyraminxFace {
+    /// F 面 (B 頂点の対面)
+    Front,
+
+    /// L 面 (R 頂点の対面)
+    Left,
+
+    /// R 面 (L 頂点の対面)
+    Right,
+
+    /// D 面 (U 頂点の対面)
+    Down,
+}
+
+impl PyraminxFace {
+    /// 全ての面。
+    pub const ALL: [PyraminxFace; 4] = [
+        PyraminxFace::Front,
+        PyraminxFace::Left,
+        PyraminxFace::Right,
+        PyraminxFace::Down,
+    ];
+
+    /// 面を構成する頂点を、外側から見て反時計回りに返す。
+    /// 先頭は F 面を中央に置いた展開図で F 面から最も遠い頂点 (F 面では U 頂点) になる。
+    pub(crate) fn vertices(self) -> [PyraminxVertex; 3] {
+        match self {
+            PyraminxFace::Front => [
+                PyraminxVertex::Up,
+                PyraminxVertex::Left,
+                PyraminxVertex::Right,
+            ],
+            PyraminxFace::Left => [
+                PyraminxVertex::Back,
+                PyraminxVertex::Left,
+                PyraminxVertex::Up,
+            ],
+            PyraminxFace::Right => [
+                PyraminxVertex::Back,
+                PyraminxVertex::Up,
+                PyraminxVertex::Right,
+            ],
+            PyraminxFace::Down => [
+                PyraminxVertex::Back,
+                PyraminxVertex::Right,
+                PyraminxVertex::Left,
+            ],
+        }
+    }
+}
+
+/// ピラミンクスの 1 つの操作を表す。
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct PyraminxMovement {
+    /// 回転する頂点
+    pub vertex: PyraminxVertex,
+
+    /// 先端 (小文字の記号) のみを回転するかどうか
+    pub tip: bool,
+
+    /// 頂点側から見た回転方向。
+    pub direction: Rotation,
+}
+
+impl PyraminxMovement {
+    /// 逆操作を返す。
+    pub fn inverse(&self) -> PyraminxMovement {
+        PyraminxMovement {
+            direction: match self.direction {
+                Rotation::Clockwise => Rotation::Counterclockwise,
+                Rotation::Counterclockwise | Rotation::Turnover => Rotation::Clockwise,
+            },
+            ..*self
+        }
+    }
+}
+
+impl Display for PyraminxMovement {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let letter = match self.vertex {
+            PyraminxVertex::Up => 'U',
+            PyraminxVertex::Left => 'L',
+            PyraminxVertex::Right => 'R',
+            PyraminxVertex::Back => 'B',
+        };
+        if self.tip {
+            write!(f, "{}", letter.to_ascii_lowercase())?;
+        } else {
+            write!(f, "{}", letter)?;
+        }
+        match self.direction {
+            Rotation::Clockwise => Ok(()),
+            Rotation::Counterclockwise => write!(f, "'"),
+            Rotation::Turnover => write!(f, "2"),
+        }
+    }
+}
+
+/// ピラミンクスの回転記号をパースする。
+/// `U L R B` が 2 層、小文字の `u l r b` が先端のみの回転を表す。
+pub fn parse_pyraminx_movements(source: &str) -> Result<Vec<PyraminxMovement>, MovementParseError> {
+    let movements = parse_letter_movements(source, |c| {
+        let vertex = match c.to_ascii_uppercase() {
+            'U' => PyraminxVertex::Up,
+            'L' => PyraminxVertex::Left,
+            'R' => PyraminxVertex::Right,
+            'B' => PyraminxVertex::Back,
+            _ => return None,
+        };
+        Some((vertex, c.is_ascii_lowercase()))
+    })?;
+    Ok(movements
+        .into_iter()
+        .map(|((vertex, tip), direction)| PyraminxMovement {
+            vertex,
+            tip,
+            direction,
+        })
+        .collect())
+}
+
+/// ピラミンクスの状態を表す。
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Pyraminx {
+    /// `PyraminxFace::ALL` の順に、各面のステッカーを `TRIANGLES` の順で並べたもの
+    stickers: Vec<Sticker<PyraminxFace>>,
+}
+
+impl Default for Pyraminx {
+    fn default() -> Pyraminx {
+        Pyraminx::new()
+    }
+}
+
+impl Pyraminx {
+    /// 揃った状態のピラミンクスを生成する。
+    pub fn new() -> Pyraminx {
+        let stickers = PyraminxFace::ALL
+            .iter()
+            .flat_map(|&face| vec![Sticker::Face(face); STICKERS_PER_FACE])
+            .collect();
+        Pyraminx { stickers }
+    }
+
+    /// 指定した位置のステッカーを返す。
+    pub fn sticker(&self, face: PyraminxFace, index: usize) -> Option<&Sticker<PyraminxFace>> {
+        if index >= STICKERS_PER_FACE {
+            return None;
+        }
+        Some(&self.stickers[face_offset(face) + index])
+    }
+
+    /// 操作を適用する。
+    pub fn apply(&mut self, movement: PyraminxMovement) {
+        let count = match movement.direction {
+            Rotation::Clockwise => 1.0,
+            Rotation::Counterclockwise | Rotation::Turnover => 2.0,
+        };
+
+        // 頂点から対面までを 3 等分した層のうち、先端は 1 層、通常の回転は 2 層を回す
+        let axis = movement.vertex.position();
+        let radius = 3.0f64.sqrt();
+        let depth = if movement.tip {
+            radius * 5.0 / 9.0
+        } else {
+            radius / 9.0
+        };
+        let targets = twist_targets(&centroids(), axis, depth, -2.0 * PI / 3.0 * count);
+        permute(&mut self.stickers, &targets);
+    }
+}
+
+impl Puzzle for Pyraminx {
+    type Movement = PyraminxMovement;
+    type Face = PyraminxFace;
+    type ParseError = MovementParseError;
+    type Error = Infallible;
+
+    const FACES: &'static [PyraminxFace] = &PyraminxFace::ALL;
+
+    fn parse_movements(source: &str) -> Result<Vec<PyraminxMovement>, MovementParseError> {
+        parse_pyraminx_movements(source)
+    }
+
+    fn inverse_movement(movement: PyraminxMovement) -> PyraminxMovement {
+        movement.inverse()
+    }
+
+    fn apply_movement(&mut self, movement: PyraminxMovement) -> Result<(), Infallible> {
+        self.apply(movement);
+        Ok(())
+    }
+
+    fn stickers(&self, face: PyraminxFace) -> &[Sticker<PyraminxFace>] {
+        let offset = face_offset(face);
+        &self.stickers[offset..offset + STICKERS_PER_FACE]
+    }
+}
+
+/// 面のステッカーの先頭のインデックスを返す。
+fn face_offset(face: PyraminxFace) -> usize {
+    let index = PyraminxFace::ALL
+        .iter()
+        .position(|&f| f == face)
+        .expect("Every face should be listed");
+    index * STICKERS_PER_FACE
+}
+
+/// 全てのステッカーの重心を求める。
+fn centroids() -> Vec<Point> {
+    PyraminxFace::ALL
+        .iter()
+        .flat_map(|face| triangle_centroids(face.vertices().map(PyraminxVertex::position)))
+        .collect()
+}
@@ -0,0 +1,209 @@
+use super::{
+    geometry::{permute, twist_targets, Point},
+    parse_letter_movements, Puzzle,
+};
+use crate::{
+    cube::{CubeFace, Sticker},
+    notation::{MovementParseError, Rotation},
+};
+use std::{
+    convert::Infallible,
+    f64::consts::PI,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+/// 1 つの面のステッカーの数。
+const STICKERS_PER_FACE: usize = 5;
+
+/// 面上のステッカーの配置。
+/// 外側から見た (右方向, 下方向) の符号で表し、中央の正方形を先頭に左上から時計回りに角の三角形を並べる。
+pub(crate) const SKEWB_STICKERS: [(i8, i8); STICKERS_PER_FACE] =
+    [(0, 0), (-1, -1), (1, -1), (1, 1), (-1, 1)];
+
+/// スキューブの回転軸となる角を表す。
+/// WCA の記法に従い、U 面と F 面を固定した持ち方で回転する角を指定する。
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SkewbCorner {
+    /// R (DRB の角)
+    Right,
+
+    /// L (DLF の角)
+    Left,
+
+    /// U (ULB の角)
+    Up,
+
+    /// B (DLB の角)
+    Back,
+}
+
+impl SkewbCorner {
+    /// 角の座標を返す。
+    fn position(self) -> Point {
+        match self {
+            SkewbCorner::Right => [1.0, -1.0, -1.0],
+            SkewbCorner::Left => [-1.0, -1.0, 1.0],
+            SkewbCorner::Up => [-1.0, 1.0, -1.0],
+            SkewbCorner::Back => [-1.0, -1.0, -1.0],
+        }
+    }
+}
+
+/// スキューブの 1 つの操作を表す。
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct SkewbMovement {
+    /// 回転する角
+    pub corner: SkewbCorner,
+
+    /// 角側から見た回転方向。
+    pub direction: Rotation,
+}
+
+impl SkewbMovement {
+    /// 逆操作を返す。
+    pub fn inverse(&self) -> SkewbMovement {
+        SkewbMovement {
+            direction: match self.direction {
+                Rotation::Clockwise => Rotation::Counterclockwise,
+                Rotation::Counterclockwise | Rotation::Turnover => Rotation::Clockwise,
+            },
+            ..*self
+        }
+    }
+}
+
+impl Display for SkewbMovement {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let letter = match self.corner {
+            SkewbCorner::Right => 'R',
+            SkewbCorner::Left => 'L',
+            SkewbCorner::Up => 'U',
+            SkewbCorner::Back => 'B',
+        };
+        write!(f, "{}", letter)?;
+        match self.direction {
+            Rotation::Clockwise => Ok(()),
+            Rotation::Counterclockwise => write!(f, "'"),
+            Rotation::Turnover => write!(f, "2"),
+        }
+    }
+}
+
+/// スキューブの回転記号 (`R L U B`) をパースする。
+pub fn parse_skewb_movements(source: &str) -> Result<Vec<SkewbMovement>, MovementParseError> {
+    let movements = parse_letter_movements(source, |c| match c {
+        'R' => Some(SkewbCorner::Right),
+        'L' => Some(SkewbCorner::Left),
+        'U' => Some(SkewbCorner::Up),
+        'B' => Some(SkewbCorner::Back),
+        _ => None,
+    })?;
+    Ok(movements
+        .into_iter()
+        .map(|(corner, direction)| SkewbMovement { corner, direction })
+        .collect())
+}
+
+/// スキューブの状態を表す。
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Skewb {
+    /// `CubeFace::ALL` の順に、各面のステッカーを `SKEWB_STICKERS` の順で並べたもの
+    stickers: Vec<Sticker>,
+}
+
+impl Default for Skewb {
+    fn default() -> Skewb {
+        Skewb::new()
+    }
+}
+
+impl Skewb {
+    /// 揃った状態のスキューブを生成する。
+    pub fn new() -> Skewb {
+        let stickers = CubeFace::ALL
+            .iter()
+            .flat_map(|&face| vec![Sticker::Face(face); STICKERS_PER_FACE])
+            .collect();
+        Skewb { stickers }
+    }
+
+    /// 指定した位置のステッカーを返す。
+    /// `index` は `SKEWB_STICKERS` の順に数える。
+    pub fn sticker(&self, face: CubeFace, index: usize) -> Option<&Sticker> {
+        if index >= STICKERS_PER_FACE {
+            return None;
+        }
+        Some(&self.stickers[face_offset(face) + index])
+    }
+
+    /// 操作を適用する。
+    pub fn apply(&mut self, movement: SkewbMovement) {
+        let count = match movement.direction {
+            Rotation::Clockwise => 1.0,
+            Rotation::Counterclockwise | Rotation::Turnover => 2.0,
+        };
+
+        // 中心を通り対角線に垂直な面で切った半分を回す
+        let targets = twist_targets(
+            &centroids(),
+            movement.corner.position(),
+            0.0,
+            -2.0 * PI / 3.0 * count,
+        );
+        permute(&mut self.stickers, &targets);
+    }
+}
+
+impl Puzzle for Skewb {
+    type Movement = SkewbMovement;
+    type Face = CubeFace;
+    type ParseError = MovementParseError;
+    type Error = Infallible;
+
+    const FACES: &'static [CubeFace] = &CubeFace::ALL;
+
+    fn parse_movements(source: &str) -> Result<Vec<SkewbMovement>, MovementParseError> {
+        parse_skewb_movements(source)
+    }
+
+    fn inverse_movement(movement: SkewbMovement) -> SkewbMovement {
+        movement.inverse()
+    }
+
+    fn apply_movement(&mut self, movement: SkewbMovement) -> Result<(), Infallible> {
+        self.apply(movement);
+        Ok(())
+    }
+
+    fn stickers(&self, face: CubeFace) -> &[Sticker] {
+        let offset = face_offset(face);
+        &self.stickers[offset..offset + STICKERS_PER_FACE]
+    }
+}
+
+/// 面のステッカーの先頭のインデックスを返す。
+fn face_offset(face: CubeFace) -> usize {
+    let index = CubeFace::ALL
+        .iter()
+        .position(|&f| f == face)
+        .expect("Every face should be listed");
+    index * STICKERS_PER_FACE
+}
+
+/// 全てのステッカーの重心を求める。
+/// 一辺 2 の立方体上で、角の三角形の重心は面の中心から角の方向に 2/3 の位置になる。
+fn centroids() -> Vec<Point> {
+    let mut points = Vec::with_capacity(CubeFace::ALL.len() * STICKERS_PER_FACE);
+    for face in &CubeFace::ALL {
+        let (normal, right, down) = face.frame();
+        for &(r, d) in &SKEWB_STICKERS {
+            let mut point = [0.0; 3];
+            for (k, p) in point.iter_mut().enumerate() {
+                *p = normal[k] as f64
+                    + (right[k] * r as isize + down[k] * d as isize) as f64 * 2.0 / 3.0;
+            }
+            points.push(point);
+        }
+    }
+    points
+}
@@ -0,0 +1,343 @@
+use super::Puzzle;
+use crate::cube::{CubeFace, Sticker};
+use std::{
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+/// 1 つの層の区画の数。区画は 30° ごとに分かれ、角のピースは 2 区画を占める。
+const SLOTS_PER_LAYER: usize = 12;
+
+/// 揃った状態の 1 つの層の各区画の側面の色。
+/// 区画 k は上から見て F 方向から時計回りに 15° + 30°k から 30° の範囲で、区画 0 から 5 がスライスで回転する。
+const SOLVED_SIDES: [CubeFace; SLOTS_PER_LAYER] = [
+    CubeFace::Front,
+    CubeFace::Right,
+    CubeFace::Right,
+    CubeFace::Right,
+    CubeFace::Back,
+    CubeFace::Back,
+    CubeFace::Back,
+    CubeFace::Left,
+    CubeFace::Left,
+    CubeFace::Left,
+    CubeFace::Front,
+    CubeFace::Front,
+];
+
+/// 揃った状態の 1 つの層の各区画を占めるピースの番号。
+const SOLVED_PIECES: [u8; SLOTS_PER_LAYER] = [0, 0, 1, 2, 2, 3, 4, 4, 5, 6, 6, 7];
+
+/// Square-1 の 1 つの操作を表す。
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Square1Movement {
+    /// `(x, y)` 表記の U 層と D 層の回転。
+    /// 回転量は 30° を単位とし、それぞれの層を外側から見て時計回りを正とする
+    Turn { up: i8, down: i8 },
+
+    /// `/` 表記の、右半分の 180° 回転
+    Slice,
+}
+
+impl Square1Movement {
+    /// 逆操作を返す。
+    pub fn inverse(&self) -> Square1Movement {
+        match *self {
+            Square1Movement::Turn { up, down } => Square1Movement::Turn {
+                up: -up,
+                down: -down,
+            },
+            Square1Movement::Slice => Square1Movement::Slice,
+        }
+    }
+}
+
+impl Display for Square1Movement {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Square1Movement::Turn { up, down } => write!(f, "({},{})", up, down),
+            Square1Movement::Slice => write!(f, "/"),
+        }
+    }
+}
+
+/// Square-1 の回転記号のエラーを表す。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Square1ParseError {
+    /// 不正な文字
+    InvalidCharacter(char),
+
+    /// `(x, y)` の形式になっていない、または回転量が -6 から 6 の範囲外
+    InvalidTurn(String),
+
+    /// 閉じ括弧がない
+    UnclosedParenthesis,
+}
+
+impl Display for Square1ParseError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Square1ParseError::InvalidCharacter(c) => write!(f, "Invalid character: {}", c),
+            Square1ParseError::InvalidTurn(turn) => write!(f, "Invalid turn: ({})", turn),
+            Square1ParseError::UnclosedParenthesis => write!(f, "Parenthesis is not closed"),
+        }
+    }
+}
+
+impl Error for Square1ParseError {}
+
+/// Square-1 の操作を適用する時のエラーを表す。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Square1Error {
+    /// 角のピースがスライスの切れ目をまたいでいる
+    SliceBlocked { up: bool, down: bool },
+}
+
+impl Display for Square1Error {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Square1Error::SliceBlocked { up, down } => {
+                let layers = match (up, down) {
+                    (true, true) => "U and D layers",
+                    (true, false) => "U layer",
+                    _ => "D layer",
+                };
+                write!(f, "Slice is blocked by a corner on the {}", layers)
+            }
+        }
+    }
+}
+
+impl Error for Square1Error {}
+
+/// Square-1 の回転記号をパースする。
+/// `(1,0)/ (-3,3)/` のように `(x, y)` 表記と `/` を並べたものを受け付ける。
+pub fn parse_square1_movements(source: &str) -> Result<Vec<Square1Movement>, Square1ParseError> {
+    let mut movements = vec![];
+    let mut chars = source.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '/' => movements.push(Square1Movement::Slice),
+            '(' => {
+                let mut content = String::new();
+                loop {
+                    match chars.next() {
+                        Some(')') => break,
+                        Some(c) => content.push(c),
+                        None => return Err(Square1ParseError::UnclosedParenthesis),
+                    }
+                }
+                movements.push(parse_turn(&content)?);
+            }
+            c if c.is_whitespace() => (),
+            c => return Err(Square1ParseError::InvalidCharacter(c)),
+        }
+    }
+    Ok(movements)
+}
+
+/// 括弧の中身を `(x, y)` 表記の回転としてパースする。
+fn parse_turn(content: &str) -> Result<Square1Movement, Square1ParseError> {
+    let invalid = || Square1ParseError::InvalidTurn(content.into());
+    let amounts = content
+        .split(',')
+        .map(|a| a.trim().parse::<i8>().map_err(|_| invalid()))
+        .collect::<Result<Vec<_>, _>>()?;
+    match amounts[..] {
+        [up, down] if up.abs() <= 6 && down.abs() <= 6 => Ok(Square1Movement::Turn { up, down }),
+        _ => Err(invalid()),
+    }
+}
+
+/// 層の区画を占めるピースの部分を表す。
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Square1Part {
+    /// 辺のピース
+    Edge,
+
+    /// 角のピースのうち、上から見て反時計回り側の区画
+    CornerFirst,
+
+    /// 角のピースのうち、上から見て時計回り側の区画
+    CornerSecond,
+}
+
+/// Square-1 の状態を表す。
+/// U 層と D 層はそれぞれ 12 区画に分け、どちらも上から見た時の同じ方向の区画を同じ番号で表す。
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Square1 {
+    /// 各区画を占めるピースの番号。U 層の区画の後に D 層の区画を並べる
+    pieces: [u8; SLOTS_PER_LAYER * 2],
+
+    /// 各区画の U 面または D 面のステッカー
+    stickers: Vec<Sticker>,
+
+    /// 各区画の側面のステッカー
+    sides: Vec<Sticker>,
+
+    /// 中層の右半分が反転しているかどうか
+    middle_flipped: bool,
+}
+
+impl Default for Square1 {
+    fn default() -> Square1 {
+        Square1::new()
+    }
+}
+
+impl Square1 {
+    /// 揃った状態の Square-1 を生成する。
+    pub fn new() -> Square1 {
+        let mut pieces = [0; SLOTS_PER_LAYER * 2];
+        for (i, piece) in pieces.iter_mut().enumerate() {
+            let offset = if i < SLOTS_PER_LAYER { 0 } else { 8 };
+            *piece = SOLVED_PIECES[i % SLOTS_PER_LAYER] + offset;
+        }
+        let stickers = [CubeFace::Up, CubeFace::Down]
+            .iter()
+            .flat_map(|&face| vec![Sticker::Face(face); SLOTS_PER_LAYER])
+            .collect();
+        let sides = SOLVED_SIDES
+            .iter()
+            .chain(SOLVED_SIDES.iter())
+            .map(|&face| Sticker::Face(face))
+            .collect();
+
+        Square1 {
+            pieces,
+            stickers,
+            sides,
+            middle_flipped: false,
+        }
+    }
+
+    /// 層の各区画の側面のステッカーを返す。U 面と D 面以外では空になる。
+    pub fn side_stickers(&self, face: CubeFace) -> &[Sticker] {
+        match layer_range(face) {
+            Some((start, end)) => &self.sides[start..end],
+            None => &[],
+        }
+    }
+
+    /// 層の各区画を占めるピースの部分を返す。U 面と D 面以外では空になる。
+    pub fn parts(&self, face: CubeFace) -> Vec<Square1Part> {
+        let (start, end) = match layer_range(face) {
+            Some(range) => range,
+            None => return vec![],
+        };
+        let layer = &self.pieces[start..end];
+        (0..SLOTS_PER_LAYER)
+            .map(|i| {
+                let previous = layer[(i + SLOTS_PER_LAYER - 1) % SLOTS_PER_LAYER];
+                let next = layer[(i + 1) % SLOTS_PER_LAYER];
+                if layer[i] == previous {
+                    Square1Part::CornerSecond
+                } else if layer[i] == next {
+                    Square1Part::CornerFirst
+                } else {
+                    Square1Part::Edge
+                }
+            })
+            .collect()
+    }
+
+    /// 中層の右半分が反転しているかどうかを返す。
+    pub fn is_middle_flipped(&self) -> bool {
+        self.middle_flipped
+    }
+
+    /// 角のピースに遮られずにスライスできるかどうかを返す。
+    pub fn can_slice(&self) -> bool {
+        self.blocked_layers() == (false, false)
+    }
+
+    /// 操作を適用する。
+    pub fn apply(&mut self, movement: Square1Movement) -> Result<(), Square1Error> {
+        let mut targets: Vec<usize> = (0..SLOTS_PER_LAYER * 2).collect();
+        match movement {
+            Square1Movement::Turn { up, down } => {
+                // D 層は下から見て時計回りなので、上から見ると反時計回りになる
+                let count = SLOTS_PER_LAYER as isize;
+                for i in 0..SLOTS_PER_LAYER {
+                    let slot = i as isize;
+                    targets[i] = (slot + up as isize).rem_euclid(count) as usize;
+                    targets[i + SLOTS_PER_LAYER] =
+                        (slot - down as isize).rem_euclid(count) as usize + SLOTS_PER_LAYER;
+                }
+            }
+            Square1Movement::Slice => {
+                let (up, down) = self.blocked_layers();
+                if up || down {
+                    return Err(Square1Error::SliceBlocked { up, down });
+                }
+
+                // 右半分の区画は U 層と D 層の間で順序を反転して入れ替わる
+                for i in 0..SLOTS_PER_LAYER / 2 {
+                    targets[i] = SLOTS_PER_LAYER + SLOTS_PER_LAYER / 2 - 1 - i;
+                    targets[SLOTS_PER_LAYER + i] = SLOTS_PER_LAYER / 2 - 1 - i;
+                }
+                self.middle_flipped = !self.middle_flipped;
+            }
+        }
+
+        let (pieces, stickers, sides) = (self.pieces, self.stickers.clone(), self.sides.clone());
+        for (i, &target) in targets.iter().enumerate() {
+            self.pieces[target] = pieces[i];
+            self.stickers[target] = stickers[i].clone();
+            self.sides[target] = sides[i].clone();
+        }
+        Ok(())
+    }
+
+    /// スライスの切れ目を角のピースがまたいでいる層を (U 層, D 層) で返す。
+    fn blocked_layers(&self) -> (bool, bool) {
+        let blocked = |start: usize| {
+            let layer = &self.pieces[start..start + SLOTS_PER_LAYER];
+            layer[SLOTS_PER_LAYER - 1] == layer[0]
+                || layer[SLOTS_PER_LAYER / 2 - 1] == layer[SLOTS_PER_LAYER / 2]
+        };
+        (blocked(0), blocked(SLOTS_PER_LAYER))
+    }
+}
+
+impl Puzzle for Square1 {
+    type Movement = Square1Movement;
+    type Face = CubeFace;
+    type ParseError = Square1ParseError;
+    type Error = Square1Error;
+
+    /// 側面のステッカーは層ごとに `side_stickers` で取得する。
+    const FACES: &'static [CubeFace] = &[CubeFace::Up, CubeFace::Down];
+
+    fn parse_movements(source: &str) -> Result<Vec<Square1Movement>, Square1ParseError> {
+        parse_square1_movements(source)
+    }
+
+    fn inverse_movement(movement: Square1Movement) -> Square1Movement {
+        movement.inverse()
+    }
+
+    fn apply_movement(&mut self, movement: Square1Movement) -> Result<(), Square1Error> {
+        self.apply(movement)
+    }
+
+    fn stickers(&self, face: CubeFace) -> &[Sticker] {
+        match layer_range(face) {
+            Some((start, end)) => &self.stickers[start..end],
+            None => &[],
+        }
+    }
+
+    fn is_solved(&self) -> bool {
+        *self == Square1::new()
+    }
+}
+
+/// U 面と D 面の区画の範囲を返す。
+fn layer_range(face: CubeFace) -> Option<(usize, usize)> {
+    match face {
+        CubeFace::Up => Some((0, SLOTS_PER_LAYER)),
+        CubeFace::Down => Some((SLOTS_PER_LAYER, SLOTS_PER_LAYER * 2)),
+        _ => None,
+    }
+}
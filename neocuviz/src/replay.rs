@@ -0,0 +1,149 @@
+use crate::{
+    cube::{Cube, CubeError},
+    notation::Movement,
+};
+use alloc::vec::Vec;
+
+/// 手順の途中の 1 ステップを表す。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    /// 手順中の操作のインデックス
+    pub index: usize,
+
+    /// この状態を生成した操作
+    pub movement: Movement,
+
+    /// 操作を適用した後の状態
+    pub cube: Cube,
+}
+
+/// 初期状態に手順を 1 手ずつ適用していくリプレイ。
+/// 位置は適用済みの手数を表し、0 が初期状態、`len()` が最終状態になる。
+#[derive(Debug, Clone)]
+pub struct Replay {
+    start: Cube,
+    movements: Vec<Movement>,
+    current: Cube,
+    position: usize,
+}
+
+impl Replay {
+    /// 初期状態と手順からリプレイを生成する。
+    /// 手順の全体が適用できない場合はエラーを返す。
+    pub fn new(
+        start: Cube,
+        movements: impl IntoIterator<Item = Movement>,
+    ) -> Result<Replay, CubeError> {
+        let movements: Vec<_> = movements.into_iter().collect();
+        let mut cube = start.clone();
+        for &movement in &movements {
+            cube.apply(movement)?;
+        }
+
+        Ok(Replay {
+            current: start.clone(),
+            start,
+            movements,
+            position: 0,
+        })
+    }
+
+    /// 初期状態を返す。
+    pub fn start(&self) -> &Cube {
+        &self.start
+    }
+
+    /// 手順を返す。
+    pub fn movements(&self) -> &[Movement] {
+        &self.movements
+    }
+
+    /// 手順の手数を返す。
+    pub fn len(&self) -> usize {
+        self.movements.len()
+    }
+
+    /// 手順が空かどうかを返す。
+    pub fn is_empty(&self) -> bool {
+        self.movements.is_empty()
+    }
+
+    /// 現在の位置を返す。
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// 現在の状態を返す。
+    pub fn cube(&self) -> &Cube {
+        &self.current
+    }
+
+    /// 1 手進め、そのステップを返す。
+    /// 最終状態にいる場合は `None` を返す。
+    pub fn step_forward(&mut self) -> Option<Step> {
+        let index = self.position;
+        let movement = *self.movements.get(index)?;
+        self.current
+            .apply(movement)
+            .expect("Movements should be validated");
+        self.position += 1;
+
+        Some(Step {
+            index,
+            movement,
+            cube: self.current.clone(),
+        })
+    }
+
+    /// 1 手戻し、取り消した操作を返す。
+    /// 初期状態にいる場合は `None` を返す。
+    pub fn step_backward(&mut self) -> Option<Movement> {
+        let index = self.position.checked_sub(1)?;
+        let movement = self.movements[index];
+        self.current
+            .apply(movement.inverse())
+            .expect("Inverse of validated movement should be applicable");
+        self.position = index;
+
+        Some(movement)
+    }
+
+    /// 指定した位置まで移動し、その状態を返す。
+    /// 位置が手数を超える場合は移動せずに `None` を返す。
+    pub fn seek(&mut self, position: usize) -> Option<&Cube> {
+        if position > self.movements.len() {
+            return None;
+        }
+
+        // 初期状態からやり直した方が近い場合は巻き戻す
+        if position < self.position.saturating_sub(position) {
+            self.rewind();
+        }
+        while self.position < position {
+            self.step_forward();
+        }
+        while self.position > position {
+            self.step_backward();
+        }
+        Some(&self.current)
+    }
+
+    /// 初期状態に戻す。
+    pub fn rewind(&mut self) {
+        self.current = self.start.clone();
+        self.position = 0;
+    }
+}
+
+impl Iterator for Replay {
+    type Item = Step;
+
+    fn next(&mut self) -> Option<Step> {
+        self.step_forward()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let rest = self.movements.len() - self.position;
+        (rest, Some(rest))
+    }
+}
@@ -0,0 +1,163 @@
+//! 練習や大会形式の計測のためのスクランブルの生成
+
+use crate::{
+    notation::{Face, Movement, Rotation},
+    solver::{self, parity, to_movement, CubieCube, CORNERS, EDGES},
+};
+use rand::{seq::SliceRandom, Rng};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// ランダムステートのスクランブルの最大手数。
+const RANDOM_STATE_MAX_LENGTH: usize = 21;
+
+/// WCA 規則でスクランブルに要求される、完成までの最小手数。
+const MIN_SOLUTION_LENGTH: usize = 2;
+
+/// ランダムムーブのスクランブルで回す面。同じ軸の面を U, R, F 側から順に並べる。
+const RANDOM_MOVE_FACES: [fn(usize) -> Face; 6] = [
+    Face::Up,
+    Face::Down,
+    Face::Right,
+    Face::Left,
+    Face::Front,
+    Face::Back,
+];
+
+/// 生成されたスクランブルを表す。
+/// `Display` は空白区切りの回転記号を出力する。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scramble {
+    movements: Vec<Movement>,
+}
+
+impl Scramble {
+    /// 3x3x3 キューブの全ての状態から一様に選んだ状態に至るスクランブルを生成する。
+    /// 初回の呼び出しでは探索に用いる表を生成するため時間がかかる。
+    pub fn random_state<R: Rng + ?Sized>(rng: &mut R) -> Scramble {
+        loop {
+            let state = random_cubie(rng);
+            let solution =
+                solver::solve_two_phase(&solver::tables(), &state, RANDOM_STATE_MAX_LENGTH, None)
+                    .expect("Every state should be solvable within 21 moves");
+            if solution.len() < MIN_SOLUTION_LENGTH {
+                continue;
+            }
+
+            let solution: Vec<_> = solution.into_iter().map(to_movement).collect();
+            return Scramble {
+                movements: Movement::inverse_sequence(solution.iter()).collect(),
+            };
+        }
+    }
+
+    /// `divisions` 分割のキューブに対し、WCA 規則の手数のランダムムーブのスクランブルを生成する。
+    /// 手数は 2x2x2 で 11、3x3x3 で 25、それ以上では 4x4x4 の 40 から 1 段ごとに 20 ずつ増える。
+    pub fn random_moves<R: Rng + ?Sized>(divisions: usize, rng: &mut R) -> Scramble {
+        Scramble::random_moves_with_length(divisions, random_moves_length(divisions), rng)
+    }
+
+    /// `divisions` 分割のキューブに対し、`length` 手のランダムムーブのスクランブルを生成する。
+    /// 外側から `divisions / 2` 層までのワイドムーブを含み、同じ軸の回転が続く間は
+    /// 既に回した層を再び回さない。
+    pub fn random_moves_with_length<R: Rng + ?Sized>(
+        divisions: usize,
+        length: usize,
+        rng: &mut R,
+    ) -> Scramble {
+        let max_layers = divisions / 2;
+        if max_layers == 0 {
+            return Scramble { movements: vec![] };
+        }
+
+        let mut movements = Vec::with_capacity(length);
+        let mut last_axis = None;
+        let mut turned = vec![];
+        while movements.len() < length {
+            let face = rng.gen_range(0..RANDOM_MOVE_FACES.len());
+            let layers = rng.gen_range(1..=max_layers);
+            // 偶数分割で中央まで回す場合は反対側の面と区別できないので、U, R, F 側だけを使う
+            if divisions.is_multiple_of(2) && layers == max_layers && face % 2 == 1 {
+                continue;
+            }
+
+            let axis = face / 2;
+            if last_axis != Some(axis) {
+                last_axis = Some(axis);
+                turned.clear();
+            } else if turned.contains(&(face, layers)) {
+                continue;
+            }
+            turned.push((face, layers));
+
+            let direction = match rng.gen_range(0..3) {
+                0 => Rotation::Clockwise,
+                1 => Rotation::Counterclockwise,
+                _ => Rotation::Turnover,
+            };
+            movements.push(Movement {
+                target: RANDOM_MOVE_FACES[face](layers),
+                direction,
+            });
+        }
+        Scramble { movements }
+    }
+
+    /// スクランブルの手順を返す。
+    pub fn movements(&self) -> &[Movement] {
+        &self.movements
+    }
+
+    /// スクランブルの手順を取り出す。
+    pub fn into_movements(self) -> Vec<Movement> {
+        self.movements
+    }
+}
+
+impl Display for Scramble {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        for (i, movement) in self.movements.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", movement)?;
+        }
+        Ok(())
+    }
+}
+
+/// WCA 規則でのランダムムーブのスクランブルの手数を返す。
+fn random_moves_length(divisions: usize) -> usize {
+    match divisions {
+        0 | 1 => 0,
+        2 => 11,
+        3 => 25,
+        n => 20 * (n - 2),
+    }
+}
+
+/// 解ける状態から一様に選んだ状態を生成する。
+fn random_cubie<R: Rng + ?Sized>(rng: &mut R) -> CubieCube {
+    let mut cube = CubieCube::SOLVED;
+    cube.corner_permutation.shuffle(rng);
+    cube.edge_permutation.shuffle(rng);
+    if parity(&cube.corner_permutation) != parity(&cube.edge_permutation) {
+        cube.edge_permutation.swap(0, 1);
+    }
+
+    // 最後のピースの向きは残りのピースの向きの和から決まる
+    let mut twist = 0;
+    for orientation in &mut cube.corner_orientation[..CORNERS - 1] {
+        *orientation = rng.gen_range(0..3);
+        twist += *orientation;
+    }
+    cube.corner_orientation[CORNERS - 1] = (3 - twist % 3) % 3;
+    let mut flip = 0;
+    for orientation in &mut cube.edge_orientation[..EDGES - 1] {
+        *orientation = rng.gen_range(0..2);
+        flip += *orientation;
+    }
+    cube.edge_orientation[EDGES - 1] = flip % 2;
+
+    debug_assert!(cube.is_solvable());
+    cube
+}
@@ -0,0 +1,301 @@
+//! `serde` feature で有効になる、回転記号とキューブの状態のシリアライズ
+//!
+//! 形式は以下の通りで、JSON では次のようになる。
+//!
+//! * `Movement`: 回転記号の文字列 (`"R"`, `"3Rw'"`, `"x2"` など)
+//! * `Face`: 回転方向を除いた回転記号の文字列 (`"R"`, `"Rw"`, `"M"`, `"x"` など)
+//! * `Rotation`: `"clockwise"`, `"counterclockwise"`, `"turnover"` のいずれか
+//! * `CubeFace`: 面の文字 (`"F"`, `"B"`, `"L"`, `"R"`, `"U"`, `"D"`)
+//! * `Sticker`: 面の色は面の値、不明な色は `null`、任意の色は `{"custom": "#f0f"}`
+//! * `OrientationTracking`: `"disabled"`, `"centers"`, `"all"` のいずれか
+//! * `Cube`: 以下のフィールドを持つオブジェクト
+//!   * `dimensions`: (幅, 高さ, 奥行き) の分割数 `[3, 3, 3]`。それぞれ 1 以上 256 以下
+//!   * `faces`: 面の文字から、外側から見て上の行から並べたステッカーの行の配列への対応
+//!   * `tracking`: 向きを記録する範囲
+//!   * `orientations`: `faces` と同じ形で、各ステッカーの向き (記録対象でない場合は `null`)。
+//!     `tracking` が `"disabled"` の時は省略する
+//!   * `bandages`: 融着されたピースの位置の組 `[[[0, 0, 0], [1, 0, 0]], ...]`
+//!
+//! 手順全体を 1 つの文字列として扱う場合は `#[serde(with = "neocuviz::serialization::movements")]` を使う。
+
+use crate::{
+    cube::{Cube, CubeFace, CubiePosition, OrientationTracking, Sticker},
+    notation::{Face, Movement, Movements, Rotation},
+};
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    fmt::{Formatter, Result as FmtResult},
+    marker::PhantomData,
+};
+use serde::{
+    de::{self, Deserializer, IntoDeserializer, MapAccess, Visitor},
+    ser::{SerializeMap, Serializer},
+    Deserialize, Serialize,
+};
+
+/// デシリアライズできるキューブの 1 辺の分割数の上限。
+const MAX_DIMENSION: usize = 256;
+
+impl Serialize for Movement {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Movement {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Movement, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        let mut movements = Movements::new(&source);
+        match (movements.next(), movements.next()) {
+            (Some(Ok(movement)), None) => Ok(movement),
+            (Some(Err(e)), _) => Err(de::Error::custom(e)),
+            _ => Err(de::Error::invalid_value(
+                de::Unexpected::Str(&source),
+                &"a single movement",
+            )),
+        }
+    }
+}
+
+impl Serialize for Face {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let movement = Movement {
+            target: *self,
+            direction: Rotation::Clockwise,
+        };
+        serializer.collect_str(&movement)
+    }
+}
+
+impl<'de> Deserialize<'de> for Face {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Face, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        match Movements::new(&source).collect::<Result<Vec<_>, _>>() {
+            Ok(movements) => match movements[..] {
+                [Movement {
+                    target,
+                    direction: Rotation::Clockwise,
+                }] => Ok(target),
+                _ => Err(de::Error::invalid_value(
+                    de::Unexpected::Str(&source),
+                    &"a face without rotation suffix",
+                )),
+            },
+            Err(e) => Err(de::Error::custom(e)),
+        }
+    }
+}
+
+impl Serialize for CubeFace {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for CubeFace {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<CubeFace, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        CubeFace::ALL
+            .iter()
+            .find(|face| face.to_string() == source)
+            .copied()
+            .ok_or_else(|| {
+                de::Error::invalid_value(de::Unexpected::Str(&source), &"one of F, B, L, R, U, D")
+            })
+    }
+}
+
+impl<F: Serialize> Serialize for Sticker<F> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Sticker::Face(face) => face.serialize(serializer),
+            Sticker::Unknown => serializer.serialize_none(),
+            Sticker::Custom(color) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("custom", color)?;
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'de, F: Deserialize<'de>> Deserialize<'de> for Sticker<F> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Sticker<F>, D::Error> {
+        deserializer.deserialize_any(StickerVisitor(PhantomData))
+    }
+}
+
+/// `Sticker` を読み取る `Visitor`。
+struct StickerVisitor<F>(PhantomData<F>);
+
+impl<'de, F: Deserialize<'de>> Visitor<'de> for StickerVisitor<F> {
+    type Value = Sticker<F>;
+
+    fn expecting(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "a face, null or {{\"custom\": color}}")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Sticker<F>, E> {
+        F::deserialize(value.into_deserializer()).map(Sticker::Face)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Sticker<F>, E> {
+        Ok(Sticker::Unknown)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Sticker<F>, E> {
+        Ok(Sticker::Unknown)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Sticker<F>, A::Error> {
+        let color = match map.next_entry::<String, String>()? {
+            Some((key, color)) if key == "custom" => color,
+            Some((key, _)) => return Err(de::Error::unknown_field(&key, &["custom"])),
+            None => return Err(de::Error::missing_field("custom")),
+        };
+        if map.next_key::<String>()?.is_some() {
+            return Err(de::Error::invalid_length(2, &self));
+        }
+        Ok(Sticker::Custom(color))
+    }
+}
+
+/// `Cube` のシリアライズ形式。
+#[derive(Serialize, Deserialize)]
+struct CubeRepr {
+    dimensions: [usize; 3],
+    faces: BTreeMap<CubeFace, Vec<Vec<Sticker>>>,
+    #[serde(default)]
+    tracking: OrientationTracking,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    orientations: Option<BTreeMap<CubeFace, Vec<Vec<Option<u8>>>>>,
+    #[serde(default)]
+    bandages: Vec<(CubiePosition, CubiePosition)>,
+}
+
+impl Serialize for Cube {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (width, height, depth) = self.dimensions();
+        let mut faces = BTreeMap::new();
+        let mut orientations = BTreeMap::new();
+        for &face in &CubeFace::ALL {
+            let (rows, columns) = self.face_size(face);
+            let mut sticker_rows = Vec::with_capacity(rows);
+            let mut orientation_rows = Vec::with_capacity(rows);
+            for row in 0..rows {
+                sticker_rows.push(
+                    (0..columns)
+                        .map(|column| self.sticker(face, row, column).cloned())
+                        .collect::<Option<Vec<_>>>()
+                        .expect("Sticker should exist in the face"),
+                );
+                orientation_rows.push(
+                    (0..columns)
+                        .map(|column| self.orientation(face, row, column))
+                        .collect(),
+                );
+            }
+            faces.insert(face, sticker_rows);
+            orientations.insert(face, orientation_rows);
+        }
+
+        let tracking = self.orientation_tracking();
+        CubeRepr {
+            dimensions: [width, height, depth],
+            faces,
+            tracking,
+            orientations: match tracking {
+                OrientationTracking::Disabled => None,
+                _ => Some(orientations),
+            },
+            bandages: self.bandages(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cube {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Cube, D::Error> {
+        let repr = CubeRepr::deserialize(deserializer)?;
+        let [width, height, depth] = repr.dimensions;
+        for &dimension in &repr.dimensions {
+            if dimension == 0 || dimension > MAX_DIMENSION {
+                return Err(de::Error::invalid_value(
+                    de::Unexpected::Unsigned(dimension as u64),
+                    &"a dimension from 1 to 256",
+                ));
+            }
+        }
+        let mut cube = Cube::cuboid(width, height, depth);
+        cube.set_orientation_tracking(repr.tracking);
+
+        for &face in &CubeFace::ALL {
+            let rows = repr
+                .faces
+                .get(&face)
+                .ok_or_else(|| de::Error::custom(format!("Face {} is missing", face)))?;
+            let (row_count, column_count) = cube.face_size(face);
+            if rows.len() != row_count || rows.iter().any(|r| r.len() != column_count) {
+                return Err(de::Error::custom(format!(
+                    "Face {} should have {}x{} stickers",
+                    face, row_count, column_count
+                )));
+            }
+            for (row, stickers) in rows.iter().enumerate() {
+                for (column, sticker) in stickers.iter().enumerate() {
+                    cube.set_sticker(face, row, column, sticker.clone())
+                        .map_err(de::Error::custom)?;
+                }
+            }
+        }
+
+        for (face, rows) in repr.orientations.iter().flatten() {
+            for (row, orientations) in rows.iter().enumerate() {
+                for (column, orientation) in orientations.iter().enumerate() {
+                    if let Some(orientation) = orientation {
+                        cube.set_orientation(*face, row, column, *orientation)
+                            .map_err(de::Error::custom)?;
+                    }
+                }
+            }
+        }
+
+        for (a, b) in repr.bandages {
+            cube.add_bandage(a, b).map_err(de::Error::custom)?;
+        }
+        Ok(cube)
+    }
+}
+
+/// 手順を空白区切りの 1 つの回転記号の文字列としてシリアライズする。
+/// `#[serde(with = "neocuviz::serialization::movements")]` として使う。
+pub mod movements {
+    use crate::notation::{Movement, Movements};
+    use alloc::{
+        string::{String, ToString},
+        vec::Vec,
+    };
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        movements: &[Movement],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let notation: Vec<_> = movements.iter().map(|m| m.to_string()).collect();
+        serializer.serialize_str(&notation.join(" "))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Movement>, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        Movements::new(&source)
+            .collect::<Result<_, _>>()
+            .map_err(de::Error::custom)
+    }
+}
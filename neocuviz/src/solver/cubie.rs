@@ -0,0 +1,240 @@
+//! ピースの位置と向きによる 3x3x3 キューブの表現
+
+use crate::notation::{Face, Movement, Rotation};
+
+/// 角のピースの数。
+pub(crate) const CORNERS: usize = 8;
+
+/// 辺のピースの数。
+pub(crate) const EDGES: usize = 12;
+
+/// 面の回転の数。U R F D L B の順に、時計回り、180 度、反時計回りの 3 つずつ並べる。
+pub(crate) const MOVES: usize = 18;
+
+/// E 層 (UD スライス) の辺のピースの番号の下限。
+pub(crate) const SLICE_EDGE: u8 = 8;
+
+/// ピースの位置と向きで表したキューブ。
+/// 角は URF, UFL, ULB, UBR, DFR, DLF, DBL, DRB、
+/// 辺は UR, UF, UL, UB, DR, DF, DL, DB, FR, FL, BL, BR の順に番号を付け、
+/// 各位置にあるピースの番号と、その向きを持つ。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct CubieCube {
+    pub(crate) corner_permutation: [u8; CORNERS],
+    pub(crate) corner_orientation: [u8; CORNERS],
+    pub(crate) edge_permutation: [u8; EDGES],
+    pub(crate) edge_orientation: [u8; EDGES],
+}
+
+/// U R F D L B 面の時計回りの回転。
+const BASIC_MOVES: [CubieCube; 6] = [
+    CubieCube {
+        corner_permutation: [3, 0, 1, 2, 4, 5, 6, 7],
+        corner_orientation: [0; CORNERS],
+        edge_permutation: [3, 0, 1, 2, 4, 5, 6, 7, 8, 9, 10, 11],
+        edge_orientation: [0; EDGES],
+    },
+    CubieCube {
+        corner_permutation: [4, 1, 2, 0, 7, 5, 6, 3],
+        corner_orientation: [2, 0, 0, 1, 1, 0, 0, 2],
+        edge_permutation: [8, 1, 2, 3, 11, 5, 6, 7, 4, 9, 10, 0],
+        edge_orientation: [0; EDGES],
+    },
+    CubieCube {
+        corner_permutation: [1, 5, 2, 3, 0, 4, 6, 7],
+        corner_orientation: [1, 2, 0, 0, 2, 1, 0, 0],
+        edge_permutation: [0, 9, 2, 3, 4, 8, 6, 7, 1, 5, 10, 11],
+        edge_orientation: [0, 1, 0, 0, 0, 1, 0, 0, 1, 1, 0, 0],
+    },
+    CubieCube {
+        corner_permutation: [0, 1, 2, 3, 5, 6, 7, 4],
+        corner_orientation: [0; CORNERS],
+        edge_permutation: [0, 1, 2, 3, 5, 6, 7, 4, 8, 9, 10, 11],
+        edge_orientation: [0; EDGES],
+    },
+    CubieCube {
+        corner_permutation: [0, 2, 6, 3, 4, 1, 5, 7],
+        corner_orientation: [0, 1, 2, 0, 0, 2, 1, 0],
+        edge_permutation: [0, 1, 10, 3, 4, 5, 9, 7, 8, 2, 6, 11],
+        edge_orientation: [0; EDGES],
+    },
+    CubieCube {
+        corner_permutation: [0, 1, 3, 7, 4, 5, 2, 6],
+        corner_orientation: [0, 0, 1, 2, 0, 0, 2, 1],
+        edge_permutation: [0, 1, 2, 11, 4, 5, 6, 10, 8, 9, 3, 7],
+        edge_orientation: [0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 1, 1],
+    },
+];
+
+impl Default for CubieCube {
+    fn default() -> CubieCube {
+        CubieCube::SOLVED
+    }
+}
+
+impl CubieCube {
+    /// 揃った状態。
+    pub(crate) const SOLVED: CubieCube = CubieCube {
+        corner_permutation: [0, 1, 2, 3, 4, 5, 6, 7],
+        corner_orientation: [0; CORNERS],
+        edge_permutation: [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+        edge_orientation: [0; EDGES],
+    };
+
+    /// この状態の後に `other` を適用した状態を返す。
+    pub(crate) fn multiply(&self, other: &CubieCube) -> CubieCube {
+        let mut result = CubieCube::SOLVED;
+        for i in 0..CORNERS {
+            let from = other.corner_permutation[i] as usize;
+            result.corner_permutation[i] = self.corner_permutation[from];
+            result.corner_orientation[i] =
+                (self.corner_orientation[from] + other.corner_orientation[i]) % 3;
+        }
+        for i in 0..EDGES {
+            let from = other.edge_permutation[i] as usize;
+            result.edge_permutation[i] = self.edge_permutation[from];
+            result.edge_orientation[i] =
+                (self.edge_orientation[from] + other.edge_orientation[i]) % 2;
+        }
+        result
+    }
+
+    /// 面の回転を適用した状態を返す。
+    pub(crate) fn apply_move(&self, movement: usize) -> CubieCube {
+        let basic = &BASIC_MOVES[movement / 3];
+        let mut result = *self;
+        for _ in 0..=(movement % 3) {
+            result = result.multiply(basic);
+        }
+        result
+    }
+
+    /// 角の向きの座標 (0..2187)。
+    pub(crate) fn twist(&self) -> u16 {
+        self.corner_orientation[..CORNERS - 1]
+            .iter()
+            .fold(0, |acc, &o| acc * 3 + o as u16)
+    }
+
+    /// 辺の向きの座標 (0..2048)。
+    pub(crate) fn flip(&self) -> u16 {
+        self.edge_orientation[..EDGES - 1]
+            .iter()
+            .fold(0, |acc, &o| acc * 2 + o as u16)
+    }
+
+    /// E 層の辺のピースの位置と並び順の座標 (0..11880)。
+    /// 揃った状態では 0 になり、E 層の辺が E 層にある時は 24 未満になる。
+    pub(crate) fn slice_sorted(&self) -> u16 {
+        let mut combination = 0;
+        let mut found = 0;
+        let mut order = [0; 4];
+        for position in (0..EDGES).rev() {
+            let edge = self.edge_permutation[position];
+            if edge >= SLICE_EDGE {
+                combination += binomial(EDGES - 1 - position, found + 1);
+                order[3 - found] = edge;
+                found += 1;
+            }
+        }
+        (combination * 24 + permutation_rank(&order)) as u16
+    }
+
+    /// 角の位置の座標 (0..40320)。
+    pub(crate) fn corner_permutation_rank(&self) -> u16 {
+        permutation_rank(&self.corner_permutation) as u16
+    }
+
+    /// U 層と D 層の辺の位置の座標 (0..40320)。
+    /// E 層の辺が E 層にある時のみ意味を持つ。
+    pub(crate) fn ud_edge_permutation_rank(&self) -> u16 {
+        permutation_rank(&self.edge_permutation[..SLICE_EDGE as usize]) as u16
+    }
+
+    /// 角と辺の置換の偶奇が一致し、向きの和が揃っていて、解ける状態かどうかを返す。
+    pub(crate) fn is_solvable(&self) -> bool {
+        let is_permutation = |p: &[u8]| {
+            let mut seen = 0u32;
+            for &x in p {
+                if x as usize >= p.len() || seen & (1 << x) != 0 {
+                    return false;
+                }
+                seen |= 1 << x;
+            }
+            true
+        };
+        is_permutation(&self.corner_permutation)
+            && is_permutation(&self.edge_permutation)
+            && self.corner_orientation.iter().all(|&o| o < 3)
+            && self.edge_orientation.iter().all(|&o| o < 2)
+            && self
+                .corner_orientation
+                .iter()
+                .map(|&o| o as u32)
+                .sum::<u32>()
+                % 3
+                == 0
+            && self.edge_orientation.iter().map(|&o| o as u32).sum::<u32>() % 2 == 0
+            && parity(&self.corner_permutation) == parity(&self.edge_permutation)
+    }
+}
+
+/// 直前と同じ面、または対面を逆順に回す冗長な回転かどうかを返す。
+pub(crate) fn is_redundant(last: Option<usize>, movement: usize) -> bool {
+    match last {
+        Some(last) => {
+            let (face, last_face) = (movement / 3, last / 3);
+            face == last_face || face + 3 == last_face
+        }
+        None => false,
+    }
+}
+
+/// 面の回転の番号を `Movement` に変換する。
+pub(crate) fn to_movement(movement: usize) -> Movement {
+    let target = match movement / 3 {
+        0 => Face::Up(1),
+        1 => Face::Right(1),
+        2 => Face::Front(1),
+        3 => Face::Down(1),
+        4 => Face::Left(1),
+        _ => Face::Back(1),
+    };
+    let direction = match movement % 3 {
+        0 => Rotation::Clockwise,
+        1 => Rotation::Turnover,
+        _ => Rotation::Counterclockwise,
+    };
+    Movement { target, direction }
+}
+
+/// 二項係数を求める。
+fn binomial(n: usize, k: usize) -> usize {
+    if k > n {
+        return 0;
+    }
+    (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1))
+}
+
+/// 相異なる値の並びの、辞書順での順位を求める。
+pub(crate) fn permutation_rank(values: &[u8]) -> usize {
+    let mut rank = 0;
+    for (i, &value) in values.iter().enumerate() {
+        let smaller = values[i + 1..].iter().filter(|&&v| v < value).count();
+        rank = rank * (values.len() - i) + smaller;
+    }
+    rank
+}
+
+/// 置換の偶奇を求める。奇置換なら `true` を返す。
+pub(crate) fn parity(values: &[u8]) -> bool {
+    let mut inversions = 0;
+    for i in 0..values.len() {
+        for j in i + 1..values.len() {
+            if values[i] > values[j] {
+                inversions += 1;
+            }
+        }
+    }
+    inversions % 2 == 1
+}
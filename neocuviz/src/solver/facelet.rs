@@ -0,0 +1,237 @@
+//! ステッカーの状態からピースの位置と向きへの変換
+
+use super::{
+    cubie::{CubieCube, CORNERS, EDGES},
+    SolveError,
+};
+use crate::cube::{Cube, CubeFace, CubiePosition, Sticker};
+use std::collections::BTreeMap;
+
+/// 各位置の角のピースの面。U 面または D 面から時計回りに並べる。
+const CORNER_FACES: [[CubeFace; 3]; CORNERS] = [
+    [CubeFace::Up, CubeFace::Right, CubeFace::Front],
+    [CubeFace::Up, CubeFace::Front, CubeFace::Left],
+    [CubeFace::Up, CubeFace::Left, CubeFace::Back],
+    [CubeFace::Up, CubeFace::Back, CubeFace::Right],
+    [CubeFace::Down, CubeFace::Front, CubeFace::Right],
+    [CubeFace::Down, CubeFace::Left, CubeFace::Front],
+    [CubeFace::Down, CubeFace::Back, CubeFace::Left],
+    [CubeFace::Down, CubeFace::Right, CubeFace::Back],
+];
+
+/// 各位置の辺のピースの面。向きの基準となる面を先に並べる。
+const EDGE_FACES: [[CubeFace; 2]; EDGES] = [
+    [CubeFace::Up, CubeFace::Right],
+    [CubeFace::Up, CubeFace::Front],
+    [CubeFace::Up, CubeFace::Left],
+    [CubeFace::Up, CubeFace::Back],
+    [CubeFace::Down, CubeFace::Right],
+    [CubeFace::Down, CubeFace::Front],
+    [CubeFace::Down, CubeFace::Left],
+    [CubeFace::Down, CubeFace::Back],
+    [CubeFace::Front, CubeFace::Right],
+    [CubeFace::Front, CubeFace::Left],
+    [CubeFace::Back, CubeFace::Left],
+    [CubeFace::Back, CubeFace::Right],
+];
+
+/// 2x2x2 キューブの変換で固定する角 (DBL) の位置。
+pub(crate) const FIXED_CORNER: usize = 6;
+
+impl CubieCube {
+    /// 3x3x3 キューブのステッカーの状態から変換する。
+    /// ステッカーの色はセンターの色で面を判別するため、全体回転された状態や任意の配色も扱える。
+    pub(crate) fn from_cube(cube: &Cube) -> Result<CubieCube, SolveError> {
+        if cube.dimensions() != (3, 3, 3) || !cube.bandages().is_empty() {
+            return Err(SolveError::UnsupportedCube);
+        }
+        let stickers = read_stickers(cube)?;
+
+        // センターの色と面の対応
+        let mut colors: BTreeMap<&Sticker, CubeFace> = BTreeMap::new();
+        for &face in &CubeFace::ALL {
+            let sticker = stickers[&(face, cubie_position(&[face], 3))];
+            if colors.insert(sticker, face).is_some() {
+                return Err(SolveError::InvalidPieces);
+            }
+        }
+        let colors_of = |faces: &[CubeFace]| {
+            let cubie = cubie_position(faces, 3);
+            faces
+                .iter()
+                .map(|&f| colors.get(stickers[&(f, cubie)]).copied())
+                .collect::<Option<Vec<_>>>()
+                .ok_or(SolveError::InvalidPieces)
+        };
+
+        let mut result = CubieCube::SOLVED;
+        read_corners(&mut result, colors_of)?;
+        for (i, faces) in EDGE_FACES.iter().enumerate() {
+            let colors = colors_of(faces)?;
+            let (piece, orientation) = EDGE_FACES
+                .iter()
+                .enumerate()
+                .find_map(|(j, f)| {
+                    if f[..] == colors[..] {
+                        Some((j, 0))
+                    } else if f[0] == colors[1] && f[1] == colors[0] {
+                        Some((j, 1))
+                    } else {
+                        None
+                    }
+                })
+                .ok_or(SolveError::InvalidPieces)?;
+            result.edge_permutation[i] = piece as u8;
+            result.edge_orientation[i] = orientation;
+        }
+
+        let mut edges = result.edge_permutation;
+        edges.sort_unstable();
+        if edges != CubieCube::SOLVED.edge_permutation {
+            return Err(SolveError::InvalidPieces);
+        }
+        if !result.is_solvable() {
+            return Err(SolveError::Unsolvable);
+        }
+        Ok(result)
+    }
+
+    /// 2x2x2 キューブのステッカーの状態から、辺が揃った状態として変換する。
+    /// DBL の位置にあるピースの色で D, B, L 面を、その反対色で U, F, R 面を判別するため、
+    /// 変換後の状態では DBL のピースが常に揃っている。
+    pub(crate) fn from_pocket_cube(cube: &Cube) -> Result<CubieCube, SolveError> {
+        if cube.dimensions() != (2, 2, 2) || !cube.bandages().is_empty() {
+            return Err(SolveError::UnsupportedCube);
+        }
+        let stickers = read_stickers(cube)?;
+        let corners: Vec<Vec<&Sticker>> = CORNER_FACES
+            .iter()
+            .map(|faces| {
+                let cubie = cubie_position(faces, 2);
+                faces.iter().map(|&f| stickers[&(f, cubie)]).collect()
+            })
+            .collect();
+
+        // 反対色は同じピースに含まれない
+        let fixed = &CORNER_FACES[FIXED_CORNER];
+        let mut colors: BTreeMap<&Sticker, CubeFace> = BTreeMap::new();
+        for (&sticker, &face) in corners[FIXED_CORNER].iter().zip(fixed) {
+            colors.insert(sticker, face);
+        }
+        for &sticker in stickers.values() {
+            if colors.contains_key(sticker) {
+                continue;
+            }
+            let mut opposites = fixed.iter().zip(&corners[FIXED_CORNER]).filter(|&(_, &f)| {
+                !corners
+                    .iter()
+                    .any(|corner| corner.contains(&sticker) && corner.contains(&f))
+            });
+            match (opposites.next(), opposites.next()) {
+                (Some((&face, _)), None) => colors.insert(sticker, opposite(face)),
+                _ => return Err(SolveError::InvalidPieces),
+            };
+        }
+        if colors.len() != CubeFace::ALL.len() {
+            return Err(SolveError::InvalidPieces);
+        }
+
+        let mut result = CubieCube::SOLVED;
+        read_corners(&mut result, |faces| {
+            let cubie = cubie_position(faces, 2);
+            Ok(faces
+                .iter()
+                .map(|&f| colors[stickers[&(f, cubie)]])
+                .collect())
+        })?;
+        let twist: u32 = result.corner_orientation.iter().map(|&o| o as u32).sum();
+        if !twist.is_multiple_of(3) {
+            return Err(SolveError::Unsolvable);
+        }
+        Ok(result)
+    }
+}
+
+/// 全てのステッカーを、(面, 貼られているピースの位置) ごとに読み取る。
+fn read_stickers(cube: &Cube) -> Result<BTreeMap<(CubeFace, CubiePosition), &Sticker>, SolveError> {
+    let mut stickers = BTreeMap::new();
+    for &face in &CubeFace::ALL {
+        let (rows, columns) = cube.face_size(face);
+        for row in 0..rows {
+            for column in 0..columns {
+                let sticker = match cube.sticker(face, row, column) {
+                    Some(Sticker::Unknown) | None => {
+                        return Err(SolveError::UnknownSticker { face, row, column })
+                    }
+                    Some(sticker) => sticker,
+                };
+                let cubie = cube
+                    .sticker_cubie(face, row, column)
+                    .expect("Sticker position should be valid");
+                stickers.insert((face, cubie), sticker);
+            }
+        }
+    }
+    Ok(stickers)
+}
+
+/// 角の位置ごとに、各面のステッカーの色が示す面から角のピースと向きを求める。
+fn read_corners(
+    cube: &mut CubieCube,
+    colors_of: impl Fn(&[CubeFace]) -> Result<Vec<CubeFace>, SolveError>,
+) -> Result<(), SolveError> {
+    for (i, faces) in CORNER_FACES.iter().enumerate() {
+        let colors = colors_of(faces)?;
+        let orientation = colors
+            .iter()
+            .position(|&c| c == CubeFace::Up || c == CubeFace::Down)
+            .ok_or(SolveError::InvalidPieces)?;
+        let rotated = [
+            colors[orientation],
+            colors[(orientation + 1) % 3],
+            colors[(orientation + 2) % 3],
+        ];
+        let piece = CORNER_FACES
+            .iter()
+            .position(|f| *f == rotated)
+            .ok_or(SolveError::InvalidPieces)?;
+        cube.corner_permutation[i] = piece as u8;
+        cube.corner_orientation[i] = orientation as u8;
+    }
+
+    let mut corners = cube.corner_permutation;
+    corners.sort_unstable();
+    if corners != CubieCube::SOLVED.corner_permutation {
+        return Err(SolveError::InvalidPieces);
+    }
+    Ok(())
+}
+
+/// 面の組が交わる位置のピースを返す。
+fn cubie_position(faces: &[CubeFace], divisions: usize) -> CubiePosition {
+    let middle = divisions / 2;
+    let mut position = (middle, middle, middle);
+    for face in faces {
+        match face {
+            CubeFace::Left => position.0 = 0,
+            CubeFace::Right => position.0 = divisions - 1,
+            CubeFace::Down => position.1 = 0,
+            CubeFace::Up => position.1 = divisions - 1,
+            CubeFace::Back => position.2 = 0,
+            CubeFace::Front => position.2 = divisions - 1,
+        }
+    }
+    position
+}
+
+/// 反対側の面を返す。
+fn opposite(face: CubeFace) -> CubeFace {
+    match face {
+        CubeFace::Front => CubeFace::Back,
+        CubeFace::Back => CubeFace::Front,
+        CubeFace::Left => CubeFace::Right,
+        CubeFace::Right => CubeFace::Left,
+        CubeFace::Up => CubeFace::Down,
+        CubeFace::Down => CubeFace::Up,
+    }
+}
@@ -0,0 +1,60 @@
+//! 探索に用いる表のファイルの読み書き
+
+use std::{
+    fs::File,
+    io::{prelude::*, BufReader, BufWriter, Error as IoError, ErrorKind, Result as IoResult},
+    path::Path,
+    sync::Arc,
+};
+
+/// ファイルに書き出せる表。
+pub(crate) trait TableFile: Sized {
+    /// ファイルの先頭に置く識別子。表の形式を変えた場合は更新する。
+    const MAGIC: &'static [u8; 8];
+
+    /// 識別子が一致しない場合のエラーメッセージ。
+    const MISMATCH: &'static str;
+
+    /// 識別子に続く表の本体を書き出す。
+    fn write_body(&self, writer: &mut impl Write) -> IoResult<()>;
+
+    /// `write_body` で書き出した表の本体を読み込む。
+    fn read_body(reader: &mut impl Read) -> IoResult<Self>;
+}
+
+/// `save` で書き出した表を読み込む。
+pub(crate) fn load<T: TableFile>(path: &Path) -> IoResult<T> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != T::MAGIC {
+        return Err(IoError::new(ErrorKind::InvalidData, T::MISMATCH));
+    }
+    T::read_body(&mut reader)
+}
+
+/// 表をファイルに書き出す。
+pub(crate) fn save<T: TableFile>(table: &T, path: &Path) -> IoResult<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(T::MAGIC)?;
+    table.write_body(&mut writer)?;
+    writer.flush()
+}
+
+/// 表をファイルから読み込む。
+/// ファイルが存在しないか、読み込めないか、`usable` を満たさない場合は
+/// `generate` で表を生成し、ファイルに書き出す。
+pub(crate) fn load_or_generate<T: TableFile>(
+    path: &Path,
+    usable: impl FnOnce(&T) -> bool,
+    generate: impl FnOnce() -> Arc<T>,
+) -> IoResult<Arc<T>> {
+    if let Ok(table) = load(path) {
+        if usable(&table) {
+            return Ok(Arc::new(table));
+        }
+    }
+    let table = generate();
+    save(table.as_ref(), path)?;
+    Ok(table)
+}
@@ -0,0 +1,124 @@
+//! キューブの解の探索
+
+mod cubie;
+mod facelet;
+mod file;
+mod optimal;
+mod pattern;
+mod pocket;
+mod substep;
+mod tables;
+mod two_phase;
+
+pub(crate) use cubie::{parity, to_movement, CubieCube, CORNERS, EDGES};
+pub use optimal::{OptimalParameters, OptimalSolver};
+pub use pocket::PocketCubeSolver;
+pub use substep::{Substep, SubstepParameters, SubstepSolver};
+pub(crate) use two_phase::solve as solve_two_phase;
+pub use two_phase::{TwoPhaseParameters, TwoPhaseSolver};
+
+use crate::cube::CubeFace;
+use std::{
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+    sync::{Arc, OnceLock},
+};
+use tables::Tables;
+
+/// 手数の数え方を表す。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Metric {
+    /// 180 度回転を 1 手と数える (HTM)
+    HalfTurn,
+
+    /// 180 度回転を 2 手と数える (QTM)
+    QuarterTurn,
+}
+
+/// 探索のエラーを表す。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveError {
+    /// ソルバーが対応していないサイズか、融着されたキューブ
+    UnsupportedCube,
+
+    /// 色が判別できないステッカー
+    UnknownSticker {
+        face: CubeFace,
+        row: usize,
+        column: usize,
+    },
+
+    /// 存在しないか重複したピース
+    InvalidPieces,
+
+    /// ピースの組み合わせは正しいが、揃えられない状態
+    Unsolvable,
+
+    /// 制限内で解が見つからなかった
+    NotFound,
+
+    /// 中断の要求か制限時間により探索が中断された
+    Cancelled,
+
+    /// 前提となる段階が揃っていない
+    PreviousStepUnsolved,
+
+    /// 表が指定されたメモリの上限に収まらない
+    InsufficientMemory { required: usize },
+}
+
+impl Display for SolveError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            SolveError::UnsupportedCube => {
+                write!(f, "Cube size is not supported or cube is bandaged")
+            }
+            SolveError::UnknownSticker { face, row, column } => write!(
+                f,
+                "Sticker color cannot be determined: {:?} ({}, {})",
+                face, row, column
+            ),
+            SolveError::InvalidPieces => write!(f, "Stickers do not form valid pieces"),
+            SolveError::Unsolvable => write!(f, "Cube is not solvable"),
+            SolveError::NotFound => write!(f, "No solution found within the limits"),
+            SolveError::Cancelled => write!(f, "Search was cancelled"),
+            SolveError::PreviousStepUnsolved => write!(f, "Previous step is not solved"),
+            SolveError::InsufficientMemory { required } => write!(
+                f,
+                "Memory budget is too small for the tables: {} bytes required",
+                required
+            ),
+        }
+    }
+}
+
+impl Error for SolveError {}
+
+/// 2 フェーズ法の表を返す。初回の呼び出し時に生成する。
+pub(crate) fn tables() -> Arc<Tables> {
+    static TABLES: OnceLock<Arc<Tables>> = OnceLock::new();
+    TABLES.get_or_init(|| Arc::new(Tables::generate())).clone()
+}
+
+/// テストで用いるヘルパー
+#[cfg(test)]
+mod testing {
+    use crate::{
+        cube::Cube,
+        notation::{Movement, Movements},
+    };
+
+    /// 揃った `divisions` 分割のキューブに、文字列で表した手順を適用する。
+    pub(super) fn scrambled(divisions: usize, source: &str) -> Cube {
+        let mut cube = Cube::new(divisions);
+        apply(&mut cube, Movements::new(source).map(Result::unwrap));
+        cube
+    }
+
+    /// キューブに手順を適用する。
+    pub(super) fn apply(cube: &mut Cube, movements: impl IntoIterator<Item = Movement>) {
+        for movement in movements {
+            cube.apply(movement).unwrap();
+        }
+    }
+}
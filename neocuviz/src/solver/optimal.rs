@@ -0,0 +1,576 @@
+//! パターンデータベースを用いた IDA* による 3x3x3 キューブの最適解の探索
+
+use super::{
+    cubie::{is_redundant, to_movement, CubieCube, EDGES, MOVES},
+    file::{self, TableFile},
+    pattern::{EdgePattern, PatternDatabase},
+    tables::{move_table, MoveRow, PERMUTATIONS, TWISTS},
+    SolveError,
+};
+use crate::{cube::Cube, notation::Movement};
+use std::{
+    fmt::{Debug, Formatter, Result as FmtResult},
+    io::{prelude::*, Error as IoError, ErrorKind, Result as IoResult},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// 任意の状態の最短手数の上限 (HTM)。
+const MAX_LENGTH: usize = 20;
+
+/// 中断の要求を確認する間隔 (探索したノード数)。
+const CANCEL_CHECK_INTERVAL: usize = 65536;
+
+/// 辺のパターンデータベースで追跡する辺の数の候補。大きいほど枝刈りが効く。
+const EDGE_PATTERN_SIZES: [usize; 4] = [7, 6, 5, 4];
+
+/// 角のパターンデータベースの状態数。
+const CORNER_STATES: usize = PERMUTATIONS * TWISTS;
+
+/// 最適解の探索のパラメーター。
+#[derive(Debug, Clone)]
+pub struct OptimalParameters {
+    /// 探索に用いるスレッド数
+    pub threads: usize,
+
+    /// 探索時間の上限
+    pub time_limit: Option<Duration>,
+
+    /// `true` が設定されると探索を中断するフラグ
+    pub cancel: Option<Arc<AtomicBool>>,
+}
+
+impl Default for OptimalParameters {
+    fn default() -> OptimalParameters {
+        OptimalParameters {
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            time_limit: None,
+            cancel: None,
+        }
+    }
+}
+
+/// IDA* で 3x3x3 キューブの最短手数 (HTM) の解を求めるソルバー。
+/// 角全体と、辺を 2 組に分けたパターンデータベースを枝刈りに用いる。
+/// 表の生成には数十秒から数分かかるため、`load_or_generate` でファイルに保存しておくとよい。
+#[derive(Clone)]
+pub struct OptimalSolver {
+    tables: Arc<OptimalTables>,
+}
+
+impl Debug for OptimalSolver {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.debug_struct("OptimalSolver")
+            .field("memory_usage", &self.memory_usage())
+            .finish_non_exhaustive()
+    }
+}
+
+impl OptimalSolver {
+    /// 表の大きさの既定の上限 (256 MiB)。
+    pub const DEFAULT_MEMORY_BUDGET: usize = 256 << 20;
+
+    /// 表の合計の大きさが `memory_budget` バイトに収まるように表を選んで生成する。
+    /// 最も小さい構成でも収まらない場合は `SolveError::InsufficientMemory` を返す。
+    pub fn new(memory_budget: usize) -> Result<OptimalSolver, SolveError> {
+        let (corners, edges) = OptimalTables::layout(memory_budget)?;
+        Ok(OptimalSolver {
+            tables: Arc::new(OptimalTables::generate(corners, edges)),
+        })
+    }
+
+    /// `save` で書き出した表を読み込んでソルバーを生成する。
+    pub fn load(path: impl AsRef<Path>) -> IoResult<OptimalSolver> {
+        Ok(OptimalSolver {
+            tables: Arc::new(file::load(path.as_ref())?),
+        })
+    }
+
+    /// 表をファイルに書き出す。
+    pub fn save(&self, path: impl AsRef<Path>) -> IoResult<()> {
+        file::save(self.tables.as_ref(), path.as_ref())
+    }
+
+    /// 表をファイルから読み込む。
+    /// ファイルが存在しないか、読み込めないか、`memory_budget` で選ばれる構成と異なる場合は
+    /// 表を生成し、ファイルに書き出す。
+    /// 最も小さい構成でも `memory_budget` に収まらない場合は `ErrorKind::InvalidInput` を返す。
+    pub fn load_or_generate(
+        path: impl AsRef<Path>,
+        memory_budget: usize,
+    ) -> IoResult<OptimalSolver> {
+        let layout = OptimalTables::layout(memory_budget)
+            .map_err(|e| IoError::new(ErrorKind::InvalidInput, e))?;
+        let tables = file::load_or_generate(
+            path.as_ref(),
+            |tables: &OptimalTables| tables.layout_matches(layout),
+            || Arc::new(OptimalTables::generate(layout.0, layout.1)),
+        )?;
+        Ok(OptimalSolver { tables })
+    }
+
+    /// 表の合計の大きさ (バイト) を返す。
+    pub fn memory_usage(&self) -> usize {
+        let tables = &self.tables;
+        OptimalTables::memory_usage_for(tables.corners.is_some(), tables.edge_pattern.edges())
+    }
+
+    /// キューブを揃える最短手数の手順を 1 つ返す。
+    /// 最短の解が複数ある場合は、回転の番号順で最初に見つかるものを返す。
+    pub fn solve(
+        &self,
+        cube: &Cube,
+        parameters: &OptimalParameters,
+    ) -> Result<Vec<Movement>, SolveError> {
+        let state = CubieCube::from_cube(cube)?;
+        let root = self.tables.node_of(&state);
+        let shared = Shared {
+            deadline: parameters.time_limit.map(|limit| Instant::now() + limit),
+            cancel: parameters.cancel.as_deref(),
+            aborted: AtomicBool::new(false),
+        };
+
+        for bound in self.tables.heuristic(&root) as usize..=MAX_LENGTH {
+            // 探索中はノード数の間隔ごとにしか確認しないため、深さを変える前にも確認する
+            if shared.check_aborted() {
+                return Err(SolveError::Cancelled);
+            }
+            if let Some(solution) = self.search(&state, &root, bound, parameters.threads, &shared) {
+                return Ok(solution.into_iter().map(to_movement).collect());
+            }
+            if shared.aborted.load(Ordering::Relaxed) {
+                return Err(SolveError::Cancelled);
+            }
+        }
+        Err(SolveError::NotFound)
+    }
+
+    /// `bound` 手ちょうどの解を、先頭の 2 手ごとに分けて複数のスレッドで探索する。
+    fn search(
+        &self,
+        state: &CubieCube,
+        root: &Node,
+        bound: usize,
+        threads: usize,
+        shared: &Shared,
+    ) -> Option<Vec<usize>> {
+        let prefixes = prefixes(bound.min(2));
+        let next_prefix = AtomicUsize::new(0);
+        let best = AtomicUsize::new(usize::MAX);
+        let solution = Mutex::new(None);
+
+        thread::scope(|scope| {
+            for _ in 0..threads.max(1) {
+                scope.spawn(|| {
+                    let mut worker = Worker {
+                        tables: &self.tables,
+                        shared,
+                        best: &best,
+                        state: *state,
+                        moves: Vec::with_capacity(bound),
+                        prefix_index: 0,
+                        nodes: 0,
+                    };
+                    loop {
+                        let index = next_prefix.fetch_add(1, Ordering::Relaxed);
+                        if index >= prefixes.len() || index > best.load(Ordering::Relaxed) {
+                            break;
+                        }
+                        worker.prefix_index = index;
+                        if let Some(found) = worker.search_prefix(root, &prefixes[index], bound) {
+                            let mut solution =
+                                solution.lock().expect("Lock should not be poisoned");
+                            if index < best.load(Ordering::Relaxed) {
+                                best.store(index, Ordering::Relaxed);
+                                *solution = Some(found);
+                            }
+                        }
+                        if shared.aborted.load(Ordering::Relaxed) {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+
+        solution.into_inner().expect("Lock should not be poisoned")
+    }
+}
+
+/// 探索中の状態を表す座標の組。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Node {
+    corner_permutation: u16,
+    twist: u16,
+    edges: [usize; 2],
+}
+
+/// 最適解の探索に用いる表。
+#[derive(Clone)]
+struct OptimalTables {
+    corner_moves: Vec<MoveRow>,
+    twist_moves: Vec<MoveRow>,
+    edge_pattern: EdgePattern,
+
+    /// 角全体のパターンデータベース
+    corners: Option<PatternDatabase>,
+
+    /// 番号の小さい辺と大きい辺のパターンデータベース
+    edges: [PatternDatabase; 2],
+}
+
+impl TableFile for OptimalTables {
+    const MAGIC: &'static [u8; 8] = b"NCVOPT01";
+    const MISMATCH: &'static str = "Not an optimal solver table file";
+
+    fn write_body(&self, writer: &mut impl Write) -> IoResult<()> {
+        writer.write_all(&[
+            self.corners.is_some() as u8,
+            self.edge_pattern.edges() as u8,
+        ])?;
+        if let Some(corners) = &self.corners {
+            writer.write_all(corners.as_bytes())?;
+        }
+        for database in &self.edges {
+            writer.write_all(database.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn read_body(reader: &mut impl Read) -> IoResult<OptimalTables> {
+        let mut header = [0; 2];
+        reader.read_exact(&mut header)?;
+        let (corners, edges) = (header[0] != 0, header[1] as usize);
+        if header[0] > 1 || !EDGE_PATTERN_SIZES.contains(&edges) {
+            return Err(IoError::new(ErrorKind::InvalidData, Self::MISMATCH));
+        }
+
+        let mut read_database = |size: usize| -> IoResult<PatternDatabase> {
+            let mut bytes = vec![0; PatternDatabase::bytes_for(size)];
+            reader.read_exact(&mut bytes)?;
+            Ok(PatternDatabase::from_bytes(bytes))
+        };
+        let corners = if corners {
+            Some(read_database(CORNER_STATES)?)
+        } else {
+            None
+        };
+        let size = EdgePattern::size_for(edges);
+        let edge_databases = [read_database(size)?, read_database(size)?];
+
+        let mut tables = OptimalTables::with_move_tables(edges);
+        tables.corners = corners;
+        tables.edges = edge_databases;
+        Ok(tables)
+    }
+}
+
+impl OptimalTables {
+    /// `memory_budget` に収まる構成 (角の表を用いるか, 辺の表で追跡する辺の数) を選ぶ。
+    fn layout(memory_budget: usize) -> Result<(bool, usize), SolveError> {
+        for &corners in &[true, false] {
+            for &edges in &EDGE_PATTERN_SIZES {
+                if OptimalTables::memory_usage_for(corners, edges) <= memory_budget {
+                    return Ok((corners, edges));
+                }
+            }
+        }
+        let smallest = EDGE_PATTERN_SIZES[EDGE_PATTERN_SIZES.len() - 1];
+        Err(SolveError::InsufficientMemory {
+            required: OptimalTables::memory_usage_for(false, smallest),
+        })
+    }
+
+    /// 構成から表の合計の大きさを求める。
+    /// 角の遷移表は角の表を用いない構成でも生成されるため、常に含める。
+    fn memory_usage_for(corners: bool, edges: usize) -> usize {
+        let move_bytes = (PERMUTATIONS + TWISTS) * std::mem::size_of::<MoveRow>();
+        let corner_bytes = if corners {
+            PatternDatabase::bytes_for(CORNER_STATES)
+        } else {
+            0
+        };
+        move_bytes
+            + corner_bytes
+            + PatternDatabase::bytes_for(EdgePattern::size_for(edges)) * 2
+            + EdgePattern::move_table_bytes_for(edges)
+    }
+
+    /// `layout` で選ばれた構成の表かどうかを返す。
+    fn layout_matches(&self, (corners, edges): (bool, usize)) -> bool {
+        self.corners.is_some() == corners && self.edge_pattern.edges() == edges
+    }
+
+    /// 表を生成する。
+    fn generate(corners: bool, edges: usize) -> OptimalTables {
+        let mut tables = OptimalTables::with_move_tables(edges);
+        if corners {
+            let database = PatternDatabase::generate(CORNER_STATES, 0, |index, m| {
+                tables.next_corner(index, m)
+            });
+            tables.corners = Some(database);
+        }
+        let pattern = &tables.edge_pattern;
+        let size = pattern.size();
+        let pieces = edge_pieces(edges);
+        tables.edges = [0, 1].map(|i| {
+            let start = pattern.coordinate(&CubieCube::SOLVED, &pieces[i]);
+            PatternDatabase::generate(size, start, |index, m| pattern.next(index, m))
+        });
+        tables
+    }
+
+    /// 遷移表のみを生成し、パターンデータベースが空の表を返す。
+    fn with_move_tables(edges: usize) -> OptimalTables {
+        let all_moves: Vec<usize> = (0..MOVES).collect();
+        OptimalTables {
+            corner_moves: move_table(PERMUTATIONS, &all_moves, CubieCube::corner_permutation_rank),
+            twist_moves: move_table(TWISTS, &all_moves, CubieCube::twist),
+            edge_pattern: EdgePattern::new(edges),
+            corners: None,
+            edges: [
+                PatternDatabase::from_bytes(vec![]),
+                PatternDatabase::from_bytes(vec![]),
+            ],
+        }
+    }
+
+    /// ピースの状態から座標の組を求める。
+    fn node_of(&self, state: &CubieCube) -> Node {
+        let pieces = edge_pieces(self.edge_pattern.edges());
+        Node {
+            corner_permutation: state.corner_permutation_rank(),
+            twist: state.twist(),
+            edges: [0, 1].map(|i| self.edge_pattern.coordinate(state, &pieces[i])),
+        }
+    }
+
+    /// 角の座標に回転を適用する。
+    fn next_corner(&self, index: usize, movement: usize) -> usize {
+        let (permutation, twist) = (index / TWISTS, index % TWISTS);
+        self.corner_moves[permutation][movement] as usize * TWISTS
+            + self.twist_moves[twist][movement] as usize
+    }
+
+    /// 座標の組に回転を適用する。
+    #[inline]
+    fn next(&self, node: &Node, movement: usize) -> Node {
+        Node {
+            corner_permutation: self.corner_moves[node.corner_permutation as usize][movement],
+            twist: self.twist_moves[node.twist as usize][movement],
+            edges: [
+                self.edge_pattern.next(node.edges[0], movement),
+                self.edge_pattern.next(node.edges[1], movement),
+            ],
+        }
+    }
+
+    /// 完成までの手数の下限を返す。
+    #[inline]
+    fn heuristic(&self, node: &Node) -> u8 {
+        let corners = self.corners.as_ref().map_or(0, |corners| {
+            corners.get(node.corner_permutation as usize * TWISTS + node.twist as usize)
+        });
+        corners
+            .max(self.edges[0].get(node.edges[0]))
+            .max(self.edges[1].get(node.edges[1]))
+    }
+}
+
+/// 全てのスレッドで共有する中断の状態。
+struct Shared<'a> {
+    deadline: Option<Instant>,
+    cancel: Option<&'a AtomicBool>,
+    aborted: AtomicBool,
+}
+
+impl<'a> Shared<'a> {
+    /// 中断の要求や制限時間を確認し、中断すべきかどうかを返す。
+    fn check_aborted(&self) -> bool {
+        let cancelled = self.cancel.is_some_and(|c| c.load(Ordering::Relaxed));
+        let timed_out = self.deadline.is_some_and(|d| Instant::now() >= d);
+        if cancelled || timed_out {
+            self.aborted.store(true, Ordering::Relaxed);
+        }
+        self.aborted.load(Ordering::Relaxed)
+    }
+}
+
+/// 1 つのスレッドの探索の状態。
+struct Worker<'a> {
+    tables: &'a OptimalTables,
+    shared: &'a Shared<'a>,
+    best: &'a AtomicUsize,
+    state: CubieCube,
+    moves: Vec<usize>,
+    prefix_index: usize,
+    nodes: usize,
+}
+
+impl<'a> Worker<'a> {
+    /// `prefix` から始まる `bound` 手ちょうどの解を探す。
+    fn search_prefix(&mut self, root: &Node, prefix: &[usize], bound: usize) -> Option<Vec<usize>> {
+        self.moves.clear();
+        let mut node = *root;
+        for (i, &m) in prefix.iter().enumerate() {
+            node = self.tables.next(&node, m);
+            if self.tables.heuristic(&node) as usize > bound - i - 1 {
+                return None;
+            }
+            self.moves.push(m);
+        }
+
+        if self.search(&node, bound - prefix.len()) {
+            Some(self.moves.clone())
+        } else {
+            None
+        }
+    }
+
+    /// 残り `remaining` 手ちょうどで揃う手順を探す。
+    fn search(&mut self, node: &Node, remaining: usize) -> bool {
+        if remaining == 0 {
+            return self.is_solved();
+        }
+
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(CANCEL_CHECK_INTERVAL) && self.shared.check_aborted() {
+            return false;
+        }
+        // 既により前の手順で解が見つかっている
+        if self.best.load(Ordering::Relaxed) < self.prefix_index
+            || self.shared.aborted.load(Ordering::Relaxed)
+        {
+            return false;
+        }
+
+        for m in 0..MOVES {
+            if is_redundant(self.moves.last().copied(), m) {
+                continue;
+            }
+            let next = self.tables.next(node, m);
+            if self.tables.heuristic(&next) as usize >= remaining {
+                continue;
+            }
+
+            self.moves.push(m);
+            if self.search(&next, remaining - 1) {
+                return true;
+            }
+            self.moves.pop();
+        }
+        false
+    }
+
+    /// 手順で実際に揃うかどうかを確かめる。
+    /// 辺の表が全ての辺を追跡しない構成では、下限が 0 でも揃っているとは限らない。
+    fn is_solved(&self) -> bool {
+        let cube = self
+            .moves
+            .iter()
+            .fold(self.state, |cube, &m| cube.apply_move(m));
+        cube == CubieCube::SOLVED
+    }
+}
+
+/// 冗長でない `length` 手の手順を全て返す。
+fn prefixes(length: usize) -> Vec<Vec<usize>> {
+    let mut prefixes = vec![vec![]];
+    for _ in 0..length {
+        let mut longer = vec![];
+        for prefix in &prefixes {
+            for m in 0..MOVES {
+                if !is_redundant(prefix.last().copied(), m) {
+                    let mut next = prefix.clone();
+                    next.push(m);
+                    longer.push(next);
+                }
+            }
+        }
+        prefixes = longer;
+    }
+    prefixes
+}
+
+/// 2 つの辺の表が追跡するピースの番号。
+fn edge_pieces(edges: usize) -> [Vec<u8>; 2] {
+    [
+        (0..edges as u8).collect(),
+        (EDGES as u8 - edges as u8..EDGES as u8).collect(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::testing::{apply, scrambled};
+    use std::{process, sync::OnceLock};
+
+    /// 最も小さい構成のソルバー。テストの間で共有する。
+    fn solver() -> &'static OptimalSolver {
+        static SOLVER: OnceLock<OptimalSolver> = OnceLock::new();
+        SOLVER
+            .get_or_init(|| OptimalSolver::new(OptimalTables::memory_usage_for(false, 4)).unwrap())
+    }
+
+    #[test]
+    fn finds_optimal_solutions() {
+        let parameters = OptimalParameters::default();
+        for &(source, distance) in &[
+            ("", 0),
+            ("R2 U2", 2),
+            ("R U R' U'", 4),
+            ("R U F' L2", 4),
+            ("D2 B' L U2 F", 5),
+        ] {
+            let mut cube = scrambled(3, source);
+            let solution = solver().solve(&cube, &parameters).unwrap();
+            assert_eq!(solution.len(), distance, "{}", source);
+            apply(&mut cube, solution);
+            assert!(cube.is_solved(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn stops_when_cancelled() {
+        let parameters = OptimalParameters {
+            cancel: Some(Arc::new(AtomicBool::new(true))),
+            ..Default::default()
+        };
+        let cube = scrambled(3, "R U2 F' L D B' R2 U' F2 L'");
+        assert_eq!(
+            solver().solve(&cube, &parameters),
+            Err(SolveError::Cancelled)
+        );
+    }
+
+    #[test]
+    fn rejects_small_budgets() {
+        let required = OptimalTables::memory_usage_for(false, 4);
+        assert_eq!(
+            OptimalSolver::new(required - 1).err(),
+            Some(SolveError::InsufficientMemory { required })
+        );
+    }
+
+    #[test]
+    fn saves_and_loads_tables() {
+        let path = std::env::temp_dir().join(format!("neocuviz-optimal-{}.bin", process::id()));
+        solver().save(&path).unwrap();
+        let loaded = OptimalSolver::load(&path);
+        let _ = std::fs::remove_file(&path);
+
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.memory_usage(), solver().memory_usage());
+        assert!(loaded.tables.corners.is_none());
+        assert_eq!(
+            loaded.tables.edges[0].as_bytes(),
+            solver().tables.edges[0].as_bytes()
+        );
+    }
+}
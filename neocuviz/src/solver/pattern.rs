@@ -0,0 +1,221 @@
+//! 最適解の探索で用いるパターンデータベース
+
+use super::cubie::{CubieCube, EDGES, MOVES};
+
+/// 未到達を表す値。
+const UNVISITED: u8 = 0xF;
+
+/// 状態から完成までの手数の下限を 4 ビットずつ詰めて格納した表。
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct PatternDatabase {
+    entries: Vec<u8>,
+}
+
+impl PatternDatabase {
+    /// 揃った状態 `start` からの幅優先探索で表を生成する。
+    /// 到達済みの状態が半分を超えた深さからは、未到達の状態から逆向きにたどる。
+    pub(crate) fn generate(
+        size: usize,
+        start: usize,
+        next: impl Fn(usize, usize) -> usize,
+    ) -> PatternDatabase {
+        let mut database = PatternDatabase {
+            entries: vec![0xFF; PatternDatabase::bytes_for(size)],
+        };
+        database.set(start, 0);
+        let mut filled = 1;
+        let mut depth = 0;
+        while filled < size {
+            let mut count = 0;
+            if filled * 2 < size {
+                for index in 0..size {
+                    if database.get(index) != depth {
+                        continue;
+                    }
+                    for m in 0..MOVES {
+                        let to = next(index, m);
+                        if database.get(to) == UNVISITED {
+                            database.set(to, depth + 1);
+                            count += 1;
+                        }
+                    }
+                }
+            } else {
+                for index in 0..size {
+                    if database.get(index) != UNVISITED {
+                        continue;
+                    }
+                    if (0..MOVES).any(|m| database.get(next(index, m)) == depth) {
+                        database.set(index, depth + 1);
+                        count += 1;
+                    }
+                }
+            }
+            if count == 0 {
+                break;
+            }
+            filled += count;
+            depth += 1;
+        }
+        database
+    }
+
+    /// 格納された表から復元する。
+    pub(crate) fn from_bytes(entries: Vec<u8>) -> PatternDatabase {
+        PatternDatabase { entries }
+    }
+
+    /// 格納された表を返す。
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        &self.entries
+    }
+
+    /// 手数の下限を返す。
+    #[inline]
+    pub(crate) fn get(&self, index: usize) -> u8 {
+        (self.entries[index / 2] >> ((index % 2) * 4)) & 0xF
+    }
+
+    fn set(&mut self, index: usize, value: u8) {
+        let shift = (index % 2) * 4;
+        let entry = &mut self.entries[index / 2];
+        *entry = (*entry & !(0xF << shift)) | (value << shift);
+    }
+
+    /// `size` 個の状態を持つ表のバイト数を返す。
+    pub(crate) fn bytes_for(size: usize) -> usize {
+        size.div_ceil(2)
+    }
+}
+
+/// 辺のピースの一部の位置と向きによる座標。
+/// 位置の並び (配置) と向きのビット列を組み合わせ、`配置 * 2^k + 向き` で表す。
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct EdgePattern {
+    /// 追跡する辺の数
+    edges: usize,
+
+    /// 各配置に回転を適用した後の配置
+    placement_moves: Vec<[u32; MOVES]>,
+
+    /// 各配置で回転によって向きが変わる辺のビット列
+    flip_masks: Vec<[u8; MOVES]>,
+}
+
+impl EdgePattern {
+    /// `edges` 個の辺を追跡する座標の遷移表を生成する。
+    pub(crate) fn new(edges: usize) -> EdgePattern {
+        // 各回転で位置 p にあるピースが移動する先と、その時の向きの変化
+        let mut destinations = [[0; EDGES]; MOVES];
+        let mut flips = [[0; EDGES]; MOVES];
+        for m in 0..MOVES {
+            let moved = CubieCube::SOLVED.apply_move(m);
+            for q in 0..EDGES {
+                let p = moved.edge_permutation[q] as usize;
+                destinations[m][p] = q as u8;
+                flips[m][p] = moved.edge_orientation[q];
+            }
+        }
+
+        let placements = EdgePattern::placements_for(edges);
+        let mut placement_moves = vec![[0; MOVES]; placements];
+        let mut flip_masks = vec![[0; MOVES]; placements];
+        let mut positions = vec![0; edges];
+        for placement in 0..placements {
+            decode_placement(placement, &mut positions);
+            for m in 0..MOVES {
+                let moved: Vec<_> = positions
+                    .iter()
+                    .map(|&p| destinations[m][p as usize])
+                    .collect();
+                placement_moves[placement][m] = encode_placement(&moved) as u32;
+                flip_masks[placement][m] = positions
+                    .iter()
+                    .enumerate()
+                    .fold(0, |mask, (i, &p)| mask | (flips[m][p as usize] << i));
+            }
+        }
+
+        EdgePattern {
+            edges,
+            placement_moves,
+            flip_masks,
+        }
+    }
+
+    /// 追跡する辺の数を返す。
+    pub(crate) fn edges(&self) -> usize {
+        self.edges
+    }
+
+    /// 座標の数を返す。
+    pub(crate) fn size(&self) -> usize {
+        EdgePattern::size_for(self.edges)
+    }
+
+    /// `edges` 個の辺を追跡する場合の配置の数。
+    pub(crate) fn placements_for(edges: usize) -> usize {
+        (EDGES - edges + 1..=EDGES).product()
+    }
+
+    /// `edges` 個の辺を追跡する場合の座標の数。
+    pub(crate) fn size_for(edges: usize) -> usize {
+        EdgePattern::placements_for(edges) << edges
+    }
+
+    /// `edges` 個の辺を追跡する場合の遷移表のバイト数。
+    pub(crate) fn move_table_bytes_for(edges: usize) -> usize {
+        EdgePattern::placements_for(edges) * MOVES * 5
+    }
+
+    /// `pieces` の番号の辺の座標を求める。
+    pub(crate) fn coordinate(&self, cube: &CubieCube, pieces: &[u8]) -> usize {
+        let mut positions = vec![0; self.edges];
+        let mut orientation = 0;
+        for (i, piece) in pieces.iter().enumerate() {
+            let position = cube
+                .edge_permutation
+                .iter()
+                .position(|p| p == piece)
+                .expect("Every edge should exist");
+            positions[i] = position as u8;
+            orientation |= (cube.edge_orientation[position] as usize) << i;
+        }
+        (encode_placement(&positions) << self.edges) | orientation
+    }
+
+    /// 回転を適用した後の座標を返す。
+    #[inline]
+    pub(crate) fn next(&self, coordinate: usize, movement: usize) -> usize {
+        let placement = coordinate >> self.edges;
+        let orientation = coordinate & ((1 << self.edges) - 1);
+        let moved = self.placement_moves[placement][movement] as usize;
+        let flip = self.flip_masks[placement][movement] as usize;
+        (moved << self.edges) | (orientation ^ flip)
+    }
+}
+
+/// 相異なる位置の並びを、12 個から選ぶ順列の辞書順での順位に変換する。
+fn encode_placement(positions: &[u8]) -> usize {
+    let mut rank = 0;
+    for (i, &position) in positions.iter().enumerate() {
+        let used = positions[..i].iter().filter(|&&p| p < position).count();
+        rank = rank * (EDGES - i) + position as usize - used;
+    }
+    rank
+}
+
+/// `encode_placement` の逆変換。
+fn decode_placement(mut rank: usize, positions: &mut [u8]) {
+    let mut digits = vec![0; positions.len()];
+    for i in (0..positions.len()).rev() {
+        let radix = EDGES - i;
+        digits[i] = rank % radix;
+        rank /= radix;
+    }
+
+    let mut available: Vec<u8> = (0..EDGES as u8).collect();
+    for (position, digit) in positions.iter_mut().zip(digits) {
+        *position = available.remove(digit);
+    }
+}
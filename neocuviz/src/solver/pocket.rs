@@ -0,0 +1,271 @@
+//! 2x2x2 キューブの最適解の探索
+
+use super::{
+    cubie::{permutation_rank, to_movement, CubieCube},
+    facelet::FIXED_CORNER,
+    tables::{move_table, pruning_table, MoveRow},
+    Metric, SolveError,
+};
+use crate::{
+    cube::Cube,
+    notation::{Movement, Rotation},
+};
+use std::{
+    collections::HashSet,
+    fmt::{Debug, Formatter, Result as FmtResult},
+};
+
+/// DBL 以外の角の位置の座標の数。
+const PERMUTATIONS: usize = 5040;
+
+/// DBL 以外の角の向きの座標の数。
+const TWISTS: usize = 729;
+
+/// 全ての状態の数。
+const STATES: usize = PERMUTATIONS * TWISTS;
+
+/// 半回転数で数える場合の回転。U, R, F 面の全ての回転。
+const HALF_TURN_MOVES: [usize; 9] = [0, 1, 2, 3, 4, 5, 6, 7, 8];
+
+/// 4 分の 1 回転数で数える場合の回転。U, R, F 面の 90 度回転。
+const QUARTER_TURN_MOVES: [usize; 6] = [0, 2, 3, 5, 6, 8];
+
+/// 2x2x2 キューブの最適解を求めるソルバー。
+/// DBL のピースを固定し、U, R, F 面の回転のみで解く。
+/// 全ての状態の最短手数の表 (約 3.6 MB) を生成するため、初期化に時間がかかる。
+#[derive(Clone)]
+pub struct PocketCubeSolver {
+    metric: Metric,
+    permutation_moves: Vec<MoveRow>,
+    twist_moves: Vec<MoveRow>,
+    distances: Vec<u8>,
+}
+
+impl Debug for PocketCubeSolver {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.debug_struct("PocketCubeSolver")
+            .field("metric", &self.metric)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PocketCubeSolver {
+    /// `metric` で手数を数えるソルバーを生成する。
+    pub fn new(metric: Metric) -> PocketCubeSolver {
+        let moves = PocketCubeSolver::moves_of(metric);
+        let permutation_moves = move_table(PERMUTATIONS, moves, CubieCube::pocket_permutation);
+        let twist_moves = move_table(TWISTS, moves, CubieCube::pocket_twist);
+        let distances = pruning_table(STATES, moves, |index, m| {
+            let (permutation, twist) = (index / TWISTS, index % TWISTS);
+            permutation_moves[permutation][m] as usize * TWISTS + twist_moves[twist][m] as usize
+        });
+
+        PocketCubeSolver {
+            metric,
+            permutation_moves,
+            twist_moves,
+            distances,
+        }
+    }
+
+    /// 手数の数え方を返す。
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    /// 最短手数を返す。
+    pub fn distance(&self, cube: &Cube) -> Result<usize, SolveError> {
+        let index = PocketCubeSolver::index_of(cube)?;
+        Ok(self.distances[index] as usize)
+    }
+
+    /// 最短手数の解を最大 `limit` 個返す。
+    /// 4 分の 1 回転数で数える場合も、同じ面の 90 度回転が続く部分は 180 度回転にまとめる。
+    pub fn solve(&self, cube: &Cube, limit: usize) -> Result<Vec<Vec<Movement>>, SolveError> {
+        let index = PocketCubeSolver::index_of(cube)?;
+        let mut search = Search {
+            solver: self,
+            limit,
+            moves: vec![],
+            solutions: vec![],
+            found: HashSet::new(),
+        };
+        search.collect(index);
+        Ok(search.solutions)
+    }
+
+    /// 手数の数え方に対応する回転を返す。
+    fn moves_of(metric: Metric) -> &'static [usize] {
+        match metric {
+            Metric::HalfTurn => &HALF_TURN_MOVES,
+            Metric::QuarterTurn => &QUARTER_TURN_MOVES,
+        }
+    }
+
+    /// キューブの状態の表のインデックスを求める。
+    fn index_of(cube: &Cube) -> Result<usize, SolveError> {
+        let state = CubieCube::from_pocket_cube(cube)?;
+        Ok(state.pocket_permutation() as usize * TWISTS + state.pocket_twist() as usize)
+    }
+
+    /// 回転を適用した後の状態のインデックスを求める。
+    fn next(&self, index: usize, movement: usize) -> usize {
+        let (permutation, twist) = (index / TWISTS, index % TWISTS);
+        self.permutation_moves[permutation][movement] as usize * TWISTS
+            + self.twist_moves[twist][movement] as usize
+    }
+}
+
+/// 最適解の列挙の状態。
+struct Search<'a> {
+    solver: &'a PocketCubeSolver,
+    limit: usize,
+    moves: Vec<usize>,
+    solutions: Vec<Vec<Movement>>,
+    found: HashSet<Vec<Movement>>,
+}
+
+impl<'a> Search<'a> {
+    /// 最短手数が 1 ずつ減る回転をたどり、揃った状態に至る手順を集める。
+    fn collect(&mut self, index: usize) {
+        if self.solutions.len() >= self.limit {
+            return;
+        }
+        let distance = self.solver.distances[index];
+        if distance == 0 {
+            let solution = merge_quarter_turns(&self.moves);
+            if self.found.insert(solution.clone()) {
+                self.solutions.push(solution);
+            }
+            return;
+        }
+
+        for &m in PocketCubeSolver::moves_of(self.solver.metric) {
+            let next = self.solver.next(index, m);
+            if self.solver.distances[next] + 1 != distance {
+                continue;
+            }
+            self.moves.push(m);
+            self.collect(next);
+            self.moves.pop();
+        }
+    }
+}
+
+/// 同じ回転が 2 回続く部分を 180 度回転にまとめる。
+/// 最短手順では同じ面の回転が 3 回以上続くことはない。
+fn merge_quarter_turns(moves: &[usize]) -> Vec<Movement> {
+    let mut result: Vec<Movement> = vec![];
+    let mut previous = None;
+    for &m in moves {
+        if previous == Some(m) {
+            if let Some(last) = result.last_mut() {
+                last.direction = Rotation::Turnover;
+            }
+            previous = None;
+        } else {
+            result.push(to_movement(m));
+            previous = Some(m);
+        }
+    }
+    result
+}
+
+impl CubieCube {
+    /// DBL 以外の角の位置の座標 (0..5040)。
+    fn pocket_permutation(&self) -> u16 {
+        let mut corners = [0; 7];
+        let others = (0..8).filter(|&i| i != FIXED_CORNER);
+        for (corner, i) in corners.iter_mut().zip(others) {
+            *corner = self.corner_permutation[i];
+        }
+        permutation_rank(&corners) as u16
+    }
+
+    /// DBL 以外の角の向きの座標 (0..729)。
+    /// DRB の角の向きは残りの角から決まる。
+    fn pocket_twist(&self) -> u16 {
+        self.corner_orientation[..FIXED_CORNER]
+            .iter()
+            .fold(0, |acc, &o| acc * 3 + o as u16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::testing::{apply, scrambled};
+
+    /// `metric` で数えた手順の手数。
+    fn length(metric: Metric, solution: &[Movement]) -> usize {
+        match metric {
+            Metric::HalfTurn => solution.len(),
+            Metric::QuarterTurn => solution
+                .iter()
+                .map(|m| match m.direction {
+                    Rotation::Turnover => 2,
+                    _ => 1,
+                })
+                .sum(),
+        }
+    }
+
+    #[test]
+    fn finds_optimal_solutions() {
+        for &(metric, cases) in &[
+            (
+                Metric::HalfTurn,
+                &[("R U R' U'", 4), ("R2 U2", 2), ("L D' B2", 3)],
+            ),
+            (
+                Metric::QuarterTurn,
+                &[("R U R' U'", 4), ("R2 U2", 4), ("L D' B2", 4)],
+            ),
+        ] {
+            let solver = PocketCubeSolver::new(metric);
+            for &(source, distance) in cases {
+                let cube = scrambled(2, source);
+                assert_eq!(solver.distance(&cube), Ok(distance), "{}", source);
+
+                let solutions = solver.solve(&cube, 5).unwrap();
+                assert!(!solutions.is_empty() && solutions.len() <= 5, "{}", source);
+                for solution in solutions {
+                    assert_eq!(length(metric, &solution), distance, "{}", source);
+                    let mut solved = cube.clone();
+                    apply(&mut solved, solution);
+                    assert!(solved.is_solved(), "{}", source);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn solves_scrambles() {
+        let solver = PocketCubeSolver::new(Metric::HalfTurn);
+        for source in &["F R' U2 F' R U' R2 F", "x y' B D2 L' F U' B2 R D'"] {
+            let mut cube = scrambled(2, source);
+            let distance = solver.distance(&cube).unwrap();
+            let solution = solver.solve(&cube, 1).unwrap().remove(0);
+            assert_eq!(solution.len(), distance, "{}", source);
+            apply(&mut cube, solution);
+            assert!(cube.is_solved(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn covers_all_states() {
+        for &(metric, diameter) in &[(Metric::HalfTurn, 11), (Metric::QuarterTurn, 14)] {
+            let solver = PocketCubeSolver::new(metric);
+            assert_eq!(solver.distances.iter().max(), Some(&diameter));
+        }
+    }
+
+    #[test]
+    fn rejects_unsupported_cubes() {
+        let solver = PocketCubeSolver::new(Metric::HalfTurn);
+        assert_eq!(
+            solver.distance(&Cube::new(3)),
+            Err(SolveError::UnsupportedCube)
+        );
+    }
+}
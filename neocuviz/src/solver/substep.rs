@@ -0,0 +1,620 @@
+//! クロスや F2L ペアなど、途中の段階の最適解の探索
+
+use super::{
+    cubie::{is_redundant, to_movement, CubieCube, CORNERS, MOVES},
+    pattern::{EdgePattern, PatternDatabase},
+    tables::{move_table, MoveRow, FLIPS},
+    SolveError,
+};
+use crate::{
+    cube::{Cube, CubeFace, Sticker},
+    notation::{Face, Movement, Rotation},
+};
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Formatter, Result as FmtResult},
+    sync::Arc,
+};
+
+/// 探索する手数の上限。
+const MAX_LENGTH: usize = 20;
+
+/// D 面のクロスの辺 (DR, DF, DL, DB) の番号。
+const CROSS_EDGES: [u8; 4] = [4, 5, 6, 7];
+
+/// EOLine の辺 (DF, DB) の番号。
+const LINE_EDGES: [u8; 2] = [5, 7];
+
+/// F2L のスロット (FR, FL, BL, BR) の角と辺の番号。
+const F2L_SLOTS: [(u8, u8); 4] = [(4, 8), (5, 9), (6, 10), (7, 11)];
+
+/// 対象の面を D 面に移す全体回転の候補。
+const BOTTOM_ROTATIONS: [&[(Face, Rotation)]; 6] = [
+    &[],
+    &[(Face::X, Rotation::Turnover)],
+    &[(Face::X, Rotation::Counterclockwise)],
+    &[(Face::X, Rotation::Clockwise)],
+    &[(Face::Z, Rotation::Clockwise)],
+    &[(Face::Z, Rotation::Counterclockwise)],
+];
+
+/// 探索する途中の段階を表す。いずれも D 面を基準として定義する。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Substep {
+    /// クロス
+    Cross,
+
+    /// クロスといずれかの F2L ペア
+    XCross,
+
+    /// 全ての辺の向き (F, B 面基準) と DF, DB の辺
+    EoLine,
+
+    /// 全ての辺の向き (F, B 面基準) とクロス
+    EoCross,
+
+    /// クロスが揃った状態から、いずれかの F2L ペア
+    FirstPair,
+}
+
+impl Substep {
+    /// D 面を保つ y 軸の回転で目標が変わらないかどうかを返す。
+    fn is_y_symmetric(self) -> bool {
+        !matches!(self, Substep::EoLine | Substep::EoCross)
+    }
+}
+
+/// 途中の段階の探索のパラメーター。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubstepParameters {
+    /// 目標を揃える面 (現在の向きでの位置)
+    pub face: CubeFace,
+
+    /// 解の前に全体回転を置き、`face` を D 面に移してから解く
+    pub pre_rotation: bool,
+
+    /// 返す解の最大数
+    pub limit: usize,
+}
+
+impl Default for SubstepParameters {
+    fn default() -> SubstepParameters {
+        SubstepParameters {
+            face: CubeFace::Down,
+            pre_rotation: false,
+            limit: 100,
+        }
+    }
+}
+
+/// 途中の段階の最短手数 (HTM) の解を全て求めるソルバー。
+#[derive(Clone)]
+pub struct SubstepSolver {
+    substep: Substep,
+
+    /// いずれかを満たせばよい目標。各目標は全ての表が 0 になれば満たされる。
+    goals: Vec<Vec<Pruning>>,
+}
+
+impl Debug for SubstepSolver {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.debug_struct("SubstepSolver")
+            .field("substep", &self.substep)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SubstepSolver {
+    /// `substep` を探索するソルバーを生成する。
+    pub fn new(substep: Substep) -> SubstepSolver {
+        let mut components = Components::default();
+        let goals = match substep {
+            Substep::Cross => vec![vec![Pruning::new(vec![components.edges(&CROSS_EDGES)])]],
+            Substep::EoLine => vec![vec![Pruning::new(vec![
+                components.flip(),
+                components.edges(&LINE_EDGES),
+            ])]],
+            Substep::EoCross => vec![vec![
+                Pruning::new(vec![components.edges(&CROSS_EDGES)]),
+                Pruning::new(vec![components.flip(), components.edges(&[4, 6])]),
+                Pruning::new(vec![components.flip(), components.edges(&LINE_EDGES)]),
+            ]],
+            Substep::XCross | Substep::FirstPair => F2L_SLOTS
+                .iter()
+                .map(|&(corner, edge)| {
+                    let mut edges = CROSS_EDGES.to_vec();
+                    edges.push(edge);
+                    vec![
+                        Pruning::new(vec![components.edges(&edges)]),
+                        Pruning::new(vec![
+                            components.edges(&CROSS_EDGES),
+                            components.corner(corner),
+                        ]),
+                    ]
+                })
+                .collect(),
+        };
+        SubstepSolver { substep, goals }
+    }
+
+    /// 探索する段階を返す。
+    pub fn substep(&self) -> Substep {
+        self.substep
+    }
+
+    /// 最短手数の解を最大 `parameters.limit` 個返す。
+    /// `pre_rotation` が指定された場合、各解は全体回転から始まる。
+    /// EOLine と EOCross では辺の向きの基準を変える y 回転も試し、より短い方の解を返す。
+    pub fn solve(
+        &self,
+        cube: &Cube,
+        parameters: &SubstepParameters,
+    ) -> Result<Vec<Vec<Movement>>, SolveError> {
+        let base = bottom_rotation(parameters.face);
+        let mut rotations = vec![base.clone()];
+        if parameters.pre_rotation && !self.substep.is_y_symmetric() {
+            let mut rotated = base;
+            rotated.push(Movement {
+                target: Face::Y,
+                direction: Rotation::Clockwise,
+            });
+            rotations.push(rotated);
+        }
+
+        let mut candidates = vec![];
+        for rotation in rotations {
+            let mut rotated = cube.clone();
+            for &movement in &rotation {
+                rotated
+                    .apply(movement)
+                    .map_err(|_| SolveError::UnsupportedCube)?;
+            }
+            let state = CubieCube::from_cube(&rotated)?;
+            if self.substep == Substep::FirstPair && !is_cross_solved(&state) {
+                return Err(SolveError::PreviousStepUnsolved);
+            }
+            candidates.push((rotation, self.search(&state, parameters.limit)));
+        }
+
+        let shortest = candidates
+            .iter()
+            .filter_map(|(_, solutions)| solutions.first().map(Vec::len))
+            .min()
+            .ok_or(SolveError::NotFound)?;
+        let mut result = vec![];
+        for (rotation, solutions) in candidates {
+            let faces = face_mapping(&rotation);
+            for solution in solutions {
+                if solution.len() != shortest || result.len() >= parameters.limit {
+                    continue;
+                }
+                let movements = solution.into_iter().map(to_movement);
+                if parameters.pre_rotation {
+                    result.push(rotation.iter().copied().chain(movements).collect());
+                } else {
+                    result.push(
+                        movements
+                            .map(|m| Movement {
+                                target: faces[&m.target],
+                                direction: m.direction,
+                            })
+                            .collect(),
+                    );
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// D 面を基準とした状態で、最短手数の解を最大 `limit` 個探す。
+    fn search(&self, state: &CubieCube, limit: usize) -> Vec<Vec<usize>> {
+        let root: Vec<Vec<usize>> = self
+            .goals
+            .iter()
+            .map(|goal| goal.iter().map(|p| p.coordinate(state)).collect())
+            .collect();
+        let mut search = Search {
+            goals: &self.goals,
+            limit,
+            moves: vec![],
+            solutions: vec![],
+        };
+        for bound in search.heuristic(&root)..=MAX_LENGTH {
+            search.search(&root, bound);
+            if !search.solutions.is_empty() {
+                break;
+            }
+        }
+        search.solutions
+    }
+}
+
+/// 最短手数の解の列挙の状態。
+struct Search<'a> {
+    goals: &'a [Vec<Pruning>],
+    limit: usize,
+    moves: Vec<usize>,
+    solutions: Vec<Vec<usize>>,
+}
+
+impl<'a> Search<'a> {
+    /// 残り `remaining` 手ちょうどで目標を満たす手順を集める。
+    fn search(&mut self, node: &[Vec<usize>], remaining: usize) {
+        if self.solutions.len() >= self.limit {
+            return;
+        }
+        if remaining == 0 {
+            if self.heuristic(node) == 0 {
+                self.solutions.push(self.moves.clone());
+            }
+            return;
+        }
+
+        for m in 0..MOVES {
+            if is_redundant(self.moves.last().copied(), m) {
+                continue;
+            }
+            let next: Vec<Vec<usize>> = self
+                .goals
+                .iter()
+                .zip(node)
+                .map(|(goal, indices)| {
+                    goal.iter()
+                        .zip(indices)
+                        .map(|(pruning, &index)| pruning.next(index, m))
+                        .collect()
+                })
+                .collect();
+            if self.heuristic(&next) >= remaining {
+                continue;
+            }
+
+            self.moves.push(m);
+            self.search(&next, remaining - 1);
+            self.moves.pop();
+        }
+    }
+
+    /// 目標までの手数の下限を返す。
+    fn heuristic(&self, node: &[Vec<usize>]) -> usize {
+        self.goals
+            .iter()
+            .zip(node)
+            .map(|(goal, indices)| {
+                goal.iter()
+                    .zip(indices)
+                    .map(|(pruning, &index)| pruning.database.get(index) as usize)
+                    .max()
+                    .unwrap_or(0)
+            })
+            .min()
+            .unwrap_or(0)
+    }
+}
+
+/// 部分的な状態の座標の成分。
+#[derive(Clone)]
+enum Component {
+    /// 一部の辺の位置と向き
+    Edges {
+        pattern: Arc<EdgePattern>,
+        pieces: Vec<u8>,
+    },
+
+    /// 1 つの角の位置と向き (`位置 * 3 + 向き`)
+    Corner {
+        piece: u8,
+        moves: Arc<Vec<[u8; MOVES]>>,
+    },
+
+    /// 全ての辺の向き
+    Flip { moves: Arc<Vec<MoveRow>> },
+}
+
+impl Component {
+    /// 座標の数を返す。
+    fn size(&self) -> usize {
+        match self {
+            Component::Edges { pattern, .. } => pattern.size(),
+            Component::Corner { .. } => CORNERS * 3,
+            Component::Flip { .. } => FLIPS,
+        }
+    }
+
+    /// ピースの状態から座標を求める。
+    fn coordinate(&self, cube: &CubieCube) -> usize {
+        match self {
+            Component::Edges { pattern, pieces } => pattern.coordinate(cube, pieces),
+            Component::Corner { piece, .. } => {
+                let position = cube
+                    .corner_permutation
+                    .iter()
+                    .position(|p| p == piece)
+                    .expect("Every corner should exist");
+                position * 3 + cube.corner_orientation[position] as usize
+            }
+            Component::Flip { .. } => cube.flip() as usize,
+        }
+    }
+
+    /// 回転を適用した後の座標を返す。
+    fn next(&self, coordinate: usize, movement: usize) -> usize {
+        match self {
+            Component::Edges { pattern, .. } => pattern.next(coordinate, movement),
+            Component::Corner { moves, .. } => moves[coordinate][movement] as usize,
+            Component::Flip { moves } => moves[coordinate][movement] as usize,
+        }
+    }
+}
+
+/// 座標の成分の遷移表を共有して生成する。
+#[derive(Default)]
+struct Components {
+    edge_patterns: HashMap<usize, Arc<EdgePattern>>,
+    corner_moves: Option<Arc<Vec<[u8; MOVES]>>>,
+    flip_moves: Option<Arc<Vec<MoveRow>>>,
+}
+
+impl Components {
+    fn edges(&mut self, pieces: &[u8]) -> Component {
+        let pattern = self
+            .edge_patterns
+            .entry(pieces.len())
+            .or_insert_with(|| Arc::new(EdgePattern::new(pieces.len())));
+        Component::Edges {
+            pattern: pattern.clone(),
+            pieces: pieces.to_vec(),
+        }
+    }
+
+    fn corner(&mut self, piece: u8) -> Component {
+        let moves = self.corner_moves.get_or_insert_with(|| {
+            let mut moves = vec![[0; MOVES]; CORNERS * 3];
+            let moved: Vec<CubieCube> = (0..MOVES)
+                .map(|m| CubieCube::SOLVED.apply_move(m))
+                .collect();
+            for (m, moved) in moved.iter().enumerate() {
+                for q in 0..CORNERS {
+                    let p = moved.corner_permutation[q] as usize;
+                    for orientation in 0..3 {
+                        let twisted = (orientation + moved.corner_orientation[q]) % 3;
+                        moves[p * 3 + orientation as usize][m] = q as u8 * 3 + twisted;
+                    }
+                }
+            }
+            Arc::new(moves)
+        });
+        Component::Corner {
+            piece,
+            moves: moves.clone(),
+        }
+    }
+
+    fn flip(&mut self) -> Component {
+        let moves = self.flip_moves.get_or_insert_with(|| {
+            let all_moves: Vec<usize> = (0..MOVES).collect();
+            Arc::new(move_table(FLIPS, &all_moves, CubieCube::flip))
+        });
+        Component::Flip {
+            moves: moves.clone(),
+        }
+    }
+}
+
+/// 成分を組み合わせた座標から、目標までの手数を引く表。
+#[derive(Clone)]
+struct Pruning {
+    components: Vec<Component>,
+    database: PatternDatabase,
+}
+
+impl Pruning {
+    fn new(components: Vec<Component>) -> Pruning {
+        let mut pruning = Pruning {
+            components,
+            database: PatternDatabase::from_bytes(vec![]),
+        };
+        let size = pruning.components.iter().map(Component::size).product();
+        let start = pruning.coordinate(&CubieCube::SOLVED);
+        pruning.database =
+            PatternDatabase::generate(size, start, |index, m| pruning.next(index, m));
+        pruning
+    }
+
+    /// ピースの状態から座標を求める。
+    fn coordinate(&self, cube: &CubieCube) -> usize {
+        self.components
+            .iter()
+            .fold(0, |index, c| index * c.size() + c.coordinate(cube))
+    }
+
+    /// 回転を適用した後の座標を返す。
+    fn next(&self, index: usize, movement: usize) -> usize {
+        let mut rest = index;
+        let mut result = 0;
+        let mut scale = 1;
+        for component in self.components.iter().rev() {
+            let size = component.size();
+            result += component.next(rest % size, movement) * scale;
+            rest /= size;
+            scale *= size;
+        }
+        result
+    }
+}
+
+/// D 面のクロスが揃っているかどうかを返す。
+fn is_cross_solved(cube: &CubieCube) -> bool {
+    CROSS_EDGES.iter().all(|&edge| {
+        let position = edge as usize;
+        cube.edge_permutation[position] == edge && cube.edge_orientation[position] == 0
+    })
+}
+
+/// `face` を D 面に移す全体回転を返す。
+fn bottom_rotation(face: CubeFace) -> Vec<Movement> {
+    BOTTOM_ROTATIONS
+        .iter()
+        .map(|rotation| {
+            rotation
+                .iter()
+                .map(|&(target, direction)| Movement { target, direction })
+                .collect::<Vec<_>>()
+        })
+        .find(|rotation| face_mapping(rotation)[&Face::Down(1)] == movement_face(face))
+        .expect("Every face should be moved to the bottom")
+}
+
+/// 全体回転の後の面の回転を、回転前の向きでの面の回転に対応付ける。
+fn face_mapping(rotation: &[Movement]) -> HashMap<Face, Face> {
+    let mut cube = Cube::new(3);
+    for &movement in rotation {
+        cube.apply(movement).expect("Rotation should be applicable");
+    }
+    CubeFace::ALL
+        .iter()
+        .map(|&face| {
+            let original = match cube.sticker(face, 1, 1) {
+                Some(Sticker::Face(original)) => *original,
+                _ => unreachable!("Center should have a face color"),
+            };
+            (movement_face(face), movement_face(original))
+        })
+        .collect()
+}
+
+/// キューブの面に対応する 1 層の回転の対象を返す。
+fn movement_face(face: CubeFace) -> Face {
+    match face {
+        CubeFace::Front => Face::Front(1),
+        CubeFace::Back => Face::Back(1),
+        CubeFace::Left => Face::Left(1),
+        CubeFace::Right => Face::Right(1),
+        CubeFace::Up => Face::Up(1),
+        CubeFace::Down => Face::Down(1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::testing::{apply, scrambled};
+
+    const SCRAMBLE: &str = "D2 F2 U' B2 R2 D F2 R2 U' L2 F' R' U' L D2 B' U2 R D' F2";
+
+    /// `face` を D 面に移した時に `substep` の目標が揃っているかどうかを返す。
+    fn is_goal(substep: Substep, cube: &Cube, face: CubeFace) -> bool {
+        let mut rotated = cube.clone();
+        apply(&mut rotated, bottom_rotation(face));
+        let state = CubieCube::from_cube(&rotated).unwrap();
+        let edge_solved = |&edge: &u8| {
+            let position = edge as usize;
+            state.edge_permutation[position] == edge && state.edge_orientation[position] == 0
+        };
+        let oriented = state.edge_orientation.iter().all(|&o| o == 0);
+        match substep {
+            Substep::Cross => is_cross_solved(&state),
+            Substep::EoLine => oriented && LINE_EDGES.iter().all(edge_solved),
+            Substep::EoCross => oriented && is_cross_solved(&state),
+            Substep::XCross | Substep::FirstPair => {
+                is_cross_solved(&state)
+                    && F2L_SLOTS.iter().any(|&(corner, edge)| {
+                        let position = corner as usize;
+                        state.corner_permutation[position] == corner
+                            && state.corner_orientation[position] == 0
+                            && edge_solved(&edge)
+                    })
+            }
+        }
+    }
+
+    /// 全体回転を除いた手数。
+    fn turns(solution: &[Movement]) -> usize {
+        solution
+            .iter()
+            .filter(|m| !matches!(m.target, Face::X | Face::Y | Face::Z))
+            .count()
+    }
+
+    /// 全ての解が同じ手数で目標を揃えることを確かめ、その手数を返す。
+    fn check_solutions(
+        solver: &SubstepSolver,
+        cube: &Cube,
+        parameters: &SubstepParameters,
+    ) -> usize {
+        let solutions = solver.solve(cube, parameters).unwrap();
+        assert!(!solutions.is_empty() && solutions.len() <= parameters.limit);
+        let length = turns(&solutions[0]);
+        for solution in solutions {
+            assert_eq!(turns(&solution), length);
+            let mut solved = cube.clone();
+            apply(&mut solved, solution);
+            let face = if parameters.pre_rotation {
+                CubeFace::Down
+            } else {
+                parameters.face
+            };
+            assert!(is_goal(solver.substep(), &solved, face), "{:?}", parameters);
+        }
+        length
+    }
+
+    #[test]
+    fn finds_known_distances() {
+        for &(substep, source, face, distance) in &[
+            (Substep::Cross, "", CubeFace::Down, 0),
+            (Substep::Cross, "R", CubeFace::Down, 1),
+            (Substep::Cross, "F", CubeFace::Up, 1),
+            (Substep::Cross, "R", CubeFace::Left, 0),
+            (Substep::EoLine, "F", CubeFace::Down, 1),
+            (Substep::EoCross, "F R", CubeFace::Down, 2),
+        ] {
+            let solver = SubstepSolver::new(substep);
+            let parameters = SubstepParameters {
+                face,
+                ..Default::default()
+            };
+            let cube = scrambled(3, source);
+            assert_eq!(
+                check_solutions(&solver, &cube, &parameters),
+                distance,
+                "{:?} {}",
+                substep,
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn solves_on_every_face() {
+        let cube = scrambled(3, SCRAMBLE);
+        for &substep in &[Substep::Cross, Substep::EoLine, Substep::EoCross] {
+            let solver = SubstepSolver::new(substep);
+            for &face in &CubeFace::ALL {
+                for &pre_rotation in &[false, true] {
+                    let parameters = SubstepParameters {
+                        face,
+                        pre_rotation,
+                        limit: 10,
+                    };
+                    let length = check_solutions(&solver, &cube, &parameters);
+                    assert!(length <= 10, "{:?} {:?}", substep, face);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn solves_xcross() {
+        let solver = SubstepSolver::new(Substep::XCross);
+        let cube = scrambled(3, SCRAMBLE);
+        for &face in &[CubeFace::Down, CubeFace::Front] {
+            let parameters = SubstepParameters {
+                face,
+                limit: 3,
+                ..Default::default()
+            };
+            check_solutions(&solver, &cube, &parameters);
+        }
+    }
+}
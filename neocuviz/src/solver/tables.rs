@@ -0,0 +1,269 @@
+//! 2 フェーズ法で用いる座標の遷移表と枝刈り表
+
+use super::{
+    cubie::{CubieCube, MOVES},
+    file::TableFile,
+};
+use std::{
+    collections::VecDeque,
+    io::{prelude::*, Error as IoError, ErrorKind, Result as IoResult},
+};
+
+/// 角の向きの座標の数。
+pub(crate) const TWISTS: usize = 2187;
+
+/// 辺の向きの座標の数。
+pub(crate) const FLIPS: usize = 2048;
+
+/// E 層の辺の位置の組み合わせの数。
+pub(crate) const SLICES: usize = 495;
+
+/// E 層の辺の位置と並び順の座標の数。
+pub(crate) const SLICE_SORTED: usize = SLICES * 24;
+
+/// 角の位置と、U 層と D 層の辺の位置の座標の数。
+pub(crate) const PERMUTATIONS: usize = 40320;
+
+/// E 層の辺の並び順の数。
+pub(crate) const SLICE_PERMUTATIONS: usize = 24;
+
+/// フェーズ 2 で用いる回転。U, D 面の全ての回転と、R, F, L, B 面の 180 度回転。
+pub(crate) const PHASE2_MOVES: [usize; 10] = [0, 1, 2, 4, 7, 9, 10, 11, 13, 16];
+
+/// 座標の遷移表の 1 行。各回転を適用した後の座標を持つ。
+pub(crate) type MoveRow = [u16; MOVES];
+
+/// 2 フェーズ法で用いる表。
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct Tables {
+    pub(crate) twist_moves: Vec<MoveRow>,
+    pub(crate) flip_moves: Vec<MoveRow>,
+    pub(crate) slice_moves: Vec<MoveRow>,
+    pub(crate) corner_moves: Vec<MoveRow>,
+    pub(crate) ud_edge_moves: Vec<MoveRow>,
+
+    /// (E 層の辺の位置, 角の向き) から、フェーズ 1 の完了までの手数の下限
+    pub(crate) twist_slice_pruning: Vec<u8>,
+
+    /// (E 層の辺の位置, 辺の向き) から、フェーズ 1 の完了までの手数の下限
+    pub(crate) flip_slice_pruning: Vec<u8>,
+
+    /// (E 層の辺の並び順, 角の位置) から、完成までの手数の下限
+    pub(crate) corner_slice_pruning: Vec<u8>,
+
+    /// (E 層の辺の並び順, U 層と D 層の辺の位置) から、完成までの手数の下限
+    pub(crate) ud_edge_slice_pruning: Vec<u8>,
+}
+
+impl Tables {
+    /// 全ての表を生成する。
+    pub(crate) fn generate() -> Tables {
+        let all_moves: Vec<usize> = (0..MOVES).collect();
+        let twist_moves = move_table(TWISTS, &all_moves, CubieCube::twist);
+        let flip_moves = move_table(FLIPS, &all_moves, CubieCube::flip);
+        let slice_moves = move_table(SLICE_SORTED, &all_moves, CubieCube::slice_sorted);
+        let corner_moves = move_table(PERMUTATIONS, &all_moves, CubieCube::corner_permutation_rank);
+        let ud_edge_moves = move_table(
+            PERMUTATIONS,
+            &PHASE2_MOVES,
+            CubieCube::ud_edge_permutation_rank,
+        );
+
+        // フェーズ 1 では E 層の辺の並び順を区別しない
+        let slice_of = |sorted: u16| sorted as usize / SLICE_PERMUTATIONS;
+        let twist_slice_pruning = pruning_table(SLICES * TWISTS, &all_moves, |index, m| {
+            let (slice, twist) = (index / TWISTS, index % TWISTS);
+            let slice = slice_of(slice_moves[slice * SLICE_PERMUTATIONS][m]);
+            slice * TWISTS + twist_moves[twist][m] as usize
+        });
+        let flip_slice_pruning = pruning_table(SLICES * FLIPS, &all_moves, |index, m| {
+            let (slice, flip) = (index / FLIPS, index % FLIPS);
+            let slice = slice_of(slice_moves[slice * SLICE_PERMUTATIONS][m]);
+            slice * FLIPS + flip_moves[flip][m] as usize
+        });
+        let corner_slice_pruning = pruning_table(
+            SLICE_PERMUTATIONS * PERMUTATIONS,
+            &PHASE2_MOVES,
+            |index, m| {
+                let (slice, corner) = (index / PERMUTATIONS, index % PERMUTATIONS);
+                slice_moves[slice][m] as usize * PERMUTATIONS + corner_moves[corner][m] as usize
+            },
+        );
+        let ud_edge_slice_pruning = pruning_table(
+            SLICE_PERMUTATIONS * PERMUTATIONS,
+            &PHASE2_MOVES,
+            |index, m| {
+                let (slice, edge) = (index / PERMUTATIONS, index % PERMUTATIONS);
+                slice_moves[slice][m] as usize * PERMUTATIONS + ud_edge_moves[edge][m] as usize
+            },
+        );
+
+        Tables {
+            twist_moves,
+            flip_moves,
+            slice_moves,
+            corner_moves,
+            ud_edge_moves,
+            twist_slice_pruning,
+            flip_slice_pruning,
+            corner_slice_pruning,
+            ud_edge_slice_pruning,
+        }
+    }
+}
+
+impl TableFile for Tables {
+    const MAGIC: &'static [u8; 8] = b"NCV2PH01";
+    const MISMATCH: &'static str = "Not a two-phase table file";
+
+    fn write_body(&self, writer: &mut impl Write) -> IoResult<()> {
+        for table in self.move_tables() {
+            for row in table {
+                for value in row {
+                    writer.write_all(&value.to_le_bytes())?;
+                }
+            }
+        }
+        for table in self.pruning_tables() {
+            writer.write_all(table)?;
+        }
+        Ok(())
+    }
+
+    fn read_body(reader: &mut impl Read) -> IoResult<Tables> {
+        // `moves` の遷移先は座標の範囲内、それ以外の回転は `u16::MAX` でなければならない
+        let all_moves: Vec<usize> = (0..MOVES).collect();
+        let mut read_moves = |size: usize, moves: &[usize]| -> IoResult<Vec<MoveRow>> {
+            let mut table = vec![[0; MOVES]; size];
+            let mut buffer = [0; 2];
+            for row in &mut table {
+                for (m, value) in row.iter_mut().enumerate() {
+                    reader.read_exact(&mut buffer)?;
+                    *value = u16::from_le_bytes(buffer);
+                    let valid = if moves.contains(&m) {
+                        (*value as usize) < size
+                    } else {
+                        *value == u16::MAX
+                    };
+                    if !valid {
+                        return Err(IoError::new(ErrorKind::InvalidData, "Invalid move table"));
+                    }
+                }
+            }
+            Ok(table)
+        };
+        let twist_moves = read_moves(TWISTS, &all_moves)?;
+        let flip_moves = read_moves(FLIPS, &all_moves)?;
+        let slice_moves = read_moves(SLICE_SORTED, &all_moves)?;
+        let corner_moves = read_moves(PERMUTATIONS, &all_moves)?;
+        let ud_edge_moves = read_moves(PERMUTATIONS, &PHASE2_MOVES)?;
+
+        // 全ての状態に到達できるため、未到達を表す `u8::MAX` は含まれない
+        let mut read_pruning = |size: usize| -> IoResult<Vec<u8>> {
+            let mut table = vec![0; size];
+            reader.read_exact(&mut table)?;
+            if table.contains(&u8::MAX) {
+                return Err(IoError::new(
+                    ErrorKind::InvalidData,
+                    "Invalid pruning table",
+                ));
+            }
+            Ok(table)
+        };
+        Ok(Tables {
+            twist_moves,
+            flip_moves,
+            slice_moves,
+            corner_moves,
+            ud_edge_moves,
+            twist_slice_pruning: read_pruning(SLICES * TWISTS)?,
+            flip_slice_pruning: read_pruning(SLICES * FLIPS)?,
+            corner_slice_pruning: read_pruning(SLICE_PERMUTATIONS * PERMUTATIONS)?,
+            ud_edge_slice_pruning: read_pruning(SLICE_PERMUTATIONS * PERMUTATIONS)?,
+        })
+    }
+}
+
+impl Tables {
+    /// 遷移表を書き出す順に返す。
+    fn move_tables(&self) -> [&[MoveRow]; 5] {
+        [
+            &self.twist_moves,
+            &self.flip_moves,
+            &self.slice_moves,
+            &self.corner_moves,
+            &self.ud_edge_moves,
+        ]
+    }
+
+    /// 枝刈り表を書き出す順に返す。
+    fn pruning_tables(&self) -> [&[u8]; 4] {
+        [
+            &self.twist_slice_pruning,
+            &self.flip_slice_pruning,
+            &self.corner_slice_pruning,
+            &self.ud_edge_slice_pruning,
+        ]
+    }
+}
+
+/// 揃った状態から `moves` で到達できる状態をたどり、座標の遷移表を生成する。
+/// `moves` に含まれない回転の遷移先は `u16::MAX` になる。
+pub(crate) fn move_table(
+    size: usize,
+    moves: &[usize],
+    coordinate: fn(&CubieCube) -> u16,
+) -> Vec<MoveRow> {
+    let mut table = vec![[u16::MAX; MOVES]; size];
+    let mut visited = vec![false; size];
+    let mut queue = VecDeque::new();
+    visited[coordinate(&CubieCube::SOLVED) as usize] = true;
+    queue.push_back(CubieCube::SOLVED);
+
+    while let Some(cube) = queue.pop_front() {
+        let from = coordinate(&cube) as usize;
+        for &m in moves {
+            let next = cube.apply_move(m);
+            let to = coordinate(&next);
+            table[from][m] = to;
+            if !visited[to as usize] {
+                visited[to as usize] = true;
+                queue.push_back(next);
+            }
+        }
+    }
+    table
+}
+
+/// 揃った状態 (インデックス 0) からの幅優先探索で枝刈り表を生成する。
+pub(crate) fn pruning_table(
+    size: usize,
+    moves: &[usize],
+    next: impl Fn(usize, usize) -> usize,
+) -> Vec<u8> {
+    let mut table = vec![u8::MAX; size];
+    table[0] = 0;
+    let mut depth = 0;
+    let mut filled = 1;
+    while filled < size {
+        let mut updated = false;
+        for index in 0..size {
+            if table[index] != depth {
+                continue;
+            }
+            for &m in moves {
+                let to = next(index, m);
+                if table[to] == u8::MAX {
+                    table[to] = depth + 1;
+                    filled += 1;
+                    updated = true;
+                }
+            }
+        }
+        if !updated {
+            break;
+        }
+        depth += 1;
+    }
+    table
+}
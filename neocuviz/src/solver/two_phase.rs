@@ -0,0 +1,320 @@
+//! Kociemba の 2 フェーズ法による探索
+
+use super::{
+    cubie::{is_redundant, to_movement, CubieCube, MOVES},
+    file,
+    tables::{Tables, FLIPS, PERMUTATIONS, PHASE2_MOVES, SLICE_PERMUTATIONS, TWISTS},
+    SolveError,
+};
+use crate::{cube::Cube, notation::Movement};
+use std::{
+    fmt::{Debug, Formatter, Result as FmtResult},
+    io::Result as IoResult,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// 制限時間を確認する間隔 (探索したノード数)。
+const DEADLINE_CHECK_INTERVAL: usize = 4096;
+
+/// 2 フェーズ法の探索のパラメーター。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TwoPhaseParameters {
+    /// 解の最大手数
+    pub max_length: usize,
+
+    /// この手数以下の解が見つかった時点で探索を終える。
+    /// 0 の場合は、より短い解がないことを確かめるか制限時間に達するまで探し続ける
+    pub target_length: usize,
+
+    /// より短い解を探し続ける時間の上限
+    pub time_limit: Duration,
+}
+
+impl Default for TwoPhaseParameters {
+    fn default() -> TwoPhaseParameters {
+        TwoPhaseParameters {
+            max_length: 21,
+            target_length: 0,
+            time_limit: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Kociemba の 2 フェーズ法による 3x3x3 キューブのソルバー。
+/// 探索に用いる表は 10 MB 程度あり、生成には数秒かかる。
+#[derive(Clone)]
+pub struct TwoPhaseSolver {
+    tables: Arc<Tables>,
+}
+
+impl Debug for TwoPhaseSolver {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.debug_struct("TwoPhaseSolver").finish_non_exhaustive()
+    }
+}
+
+impl Default for TwoPhaseSolver {
+    fn default() -> TwoPhaseSolver {
+        TwoPhaseSolver::new()
+    }
+}
+
+impl TwoPhaseSolver {
+    /// ソルバーを生成する。
+    /// 表はプロセス内で共有され、初回のみ生成される。
+    pub fn new() -> TwoPhaseSolver {
+        TwoPhaseSolver {
+            tables: super::tables(),
+        }
+    }
+
+    /// `save` で書き出した表を読み込んでソルバーを生成する。
+    pub fn load(path: impl AsRef<Path>) -> IoResult<TwoPhaseSolver> {
+        Ok(TwoPhaseSolver {
+            tables: Arc::new(file::load(path.as_ref())?),
+        })
+    }
+
+    /// 表をファイルに書き出す。
+    pub fn save(&self, path: impl AsRef<Path>) -> IoResult<()> {
+        file::save(self.tables.as_ref(), path.as_ref())
+    }
+
+    /// 表をファイルから読み込む。
+    /// ファイルが存在しないか読み込めない場合は表を生成し、ファイルに書き出す。
+    pub fn load_or_generate(path: impl AsRef<Path>) -> IoResult<TwoPhaseSolver> {
+        Ok(TwoPhaseSolver {
+            tables: file::load_or_generate(path.as_ref(), |_| true, super::tables)?,
+        })
+    }
+
+    /// キューブを揃える手順を探す。
+    /// 最初に見つかった解から、制限時間内で `target_length` 手以下になるまで短い解を探し続ける。
+    /// 手順の逆手順は、揃った状態からこの状態を作るセットアップ手順になる。
+    pub fn solve(
+        &self,
+        cube: &Cube,
+        parameters: &TwoPhaseParameters,
+    ) -> Result<Vec<Movement>, SolveError> {
+        let state = CubieCube::from_cube(cube)?;
+        let deadline = Instant::now() + parameters.time_limit;
+
+        let mut best = None;
+        let mut max_length = parameters.max_length;
+        while let Some(solution) = solve(&self.tables, &state, max_length, Some(deadline)) {
+            let length = solution.len();
+            best = Some(solution);
+            if length <= parameters.target_length || length == 0 {
+                break;
+            }
+            max_length = length - 1;
+        }
+
+        best.map(|solution| solution.into_iter().map(to_movement).collect())
+            .ok_or(SolveError::NotFound)
+    }
+}
+
+/// 2 フェーズ法の探索の状態。
+struct Search<'a> {
+    tables: &'a Tables,
+    cube: CubieCube,
+    max_length: usize,
+    moves: Vec<usize>,
+    deadline: Option<Instant>,
+    nodes: usize,
+    timed_out: bool,
+}
+
+/// `cube` を揃える `max_length` 手以下の手順を探す。
+/// 見つからないか、`deadline` を過ぎた場合は `None` を返す。
+pub(crate) fn solve(
+    tables: &Tables,
+    cube: &CubieCube,
+    max_length: usize,
+    deadline: Option<Instant>,
+) -> Option<Vec<usize>> {
+    let mut search = Search {
+        tables,
+        cube: *cube,
+        max_length,
+        moves: Vec::with_capacity(max_length),
+        deadline,
+        nodes: 0,
+        timed_out: false,
+    };
+    let (twist, flip, slice) = (cube.twist(), cube.flip(), cube.slice_sorted());
+    for depth in 0..=max_length {
+        if search.phase1(twist, flip, slice, depth) {
+            return Some(search.moves);
+        }
+        if search.timed_out {
+            break;
+        }
+    }
+    None
+}
+
+impl<'a> Search<'a> {
+    /// フェーズ 1 を `depth` 手ちょうどで探索する。
+    fn phase1(&mut self, twist: u16, flip: u16, slice: u16, depth: usize) -> bool {
+        if depth == 0 {
+            if twist != 0 || flip != 0 || slice as usize >= SLICE_PERMUTATIONS {
+                return false;
+            }
+            // フェーズ 2 の回転で終わる手順は、より短いフェーズ 1 の手順で既に探索している
+            if let Some(&last) = self.moves.last() {
+                if PHASE2_MOVES.contains(&last) {
+                    return false;
+                }
+            }
+            return self.start_phase2();
+        }
+
+        if self.is_timed_out() {
+            return false;
+        }
+        for m in 0..MOVES {
+            if is_redundant(self.moves.last().copied(), m) {
+                continue;
+            }
+            let next_twist = self.tables.twist_moves[twist as usize][m];
+            let next_flip = self.tables.flip_moves[flip as usize][m];
+            let next_slice = self.tables.slice_moves[slice as usize][m];
+            if self.phase1_distance(next_twist, next_flip, next_slice) >= depth {
+                continue;
+            }
+
+            self.moves.push(m);
+            if self.phase1(next_twist, next_flip, next_slice, depth - 1) {
+                return true;
+            }
+            self.moves.pop();
+        }
+        false
+    }
+
+    /// フェーズ 1 の手順を適用した状態からフェーズ 2 を探索する。
+    fn start_phase2(&mut self) -> bool {
+        let cube = self
+            .moves
+            .iter()
+            .fold(self.cube, |cube, &m| cube.apply_move(m));
+        let corner = cube.corner_permutation_rank();
+        let edge = cube.ud_edge_permutation_rank();
+        let slice = cube.slice_sorted();
+
+        let remaining = self.max_length - self.moves.len();
+        let lower_bound = self.phase2_distance(corner, edge, slice);
+        for depth in lower_bound..=remaining {
+            if self.phase2(corner, edge, slice, depth) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// フェーズ 2 を `depth` 手ちょうどで探索する。
+    fn phase2(&mut self, corner: u16, edge: u16, slice: u16, depth: usize) -> bool {
+        if depth == 0 {
+            return corner == 0 && edge == 0 && slice == 0;
+        }
+
+        if self.is_timed_out() {
+            return false;
+        }
+        for &m in &PHASE2_MOVES {
+            if is_redundant(self.moves.last().copied(), m) {
+                continue;
+            }
+            let next_corner = self.tables.corner_moves[corner as usize][m];
+            let next_edge = self.tables.ud_edge_moves[edge as usize][m];
+            let next_slice = self.tables.slice_moves[slice as usize][m];
+            if self.phase2_distance(next_corner, next_edge, next_slice) >= depth {
+                continue;
+            }
+
+            self.moves.push(m);
+            if self.phase2(next_corner, next_edge, next_slice, depth - 1) {
+                return true;
+            }
+            self.moves.pop();
+        }
+        false
+    }
+
+    /// 制限時間を過ぎたかどうかを返す。
+    fn is_timed_out(&mut self) -> bool {
+        self.nodes += 1;
+        if !self.timed_out && self.nodes.is_multiple_of(DEADLINE_CHECK_INTERVAL) {
+            if let Some(deadline) = self.deadline {
+                self.timed_out = Instant::now() >= deadline;
+            }
+        }
+        self.timed_out
+    }
+
+    /// フェーズ 1 の完了までの手数の下限。
+    fn phase1_distance(&self, twist: u16, flip: u16, slice: u16) -> usize {
+        let slice = slice as usize / SLICE_PERMUTATIONS;
+        let twist_slice = self.tables.twist_slice_pruning[slice * TWISTS + twist as usize];
+        let flip_slice = self.tables.flip_slice_pruning[slice * FLIPS + flip as usize];
+        twist_slice.max(flip_slice) as usize
+    }
+
+    /// フェーズ 2 の完了までの手数の下限。
+    fn phase2_distance(&self, corner: u16, edge: u16, slice: u16) -> usize {
+        let offset = slice as usize * PERMUTATIONS;
+        let corner_slice = self.tables.corner_slice_pruning[offset + corner as usize];
+        let edge_slice = self.tables.ud_edge_slice_pruning[offset + edge as usize];
+        corner_slice.max(edge_slice) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::testing::{apply, scrambled};
+
+    #[test]
+    fn solves_scrambles() {
+        let solver = TwoPhaseSolver::new();
+        let parameters = TwoPhaseParameters {
+            target_length: 21,
+            ..Default::default()
+        };
+        for source in &[
+            "R U R' U'",
+            "F2 D' L2 B2 U' R2 F2 D' L2 U2 R' F' D2 U L' B' R2 D' F' U'",
+            "U2 B2 R2 D' L2 D F2 U B2 U2 L' D2 F' L' U' B D' L' R' F U2",
+        ] {
+            let mut cube = scrambled(3, source);
+            let solution = solver.solve(&cube, &parameters).unwrap();
+            assert!(solution.len() <= 21, "{}", source);
+            apply(&mut cube, solution);
+            assert!(cube.is_solved(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn finds_short_solutions() {
+        let solver = TwoPhaseSolver::new();
+        let cube = Cube::new(3);
+        assert_eq!(solver.solve(&cube, &Default::default()), Ok(vec![]));
+
+        let mut cube = scrambled(3, "R U F' L2");
+        let solution = solver.solve(&cube, &Default::default()).unwrap();
+        assert_eq!(solution.len(), 4);
+        apply(&mut cube, solution);
+        assert!(cube.is_solved());
+    }
+
+    #[test]
+    fn rejects_unsupported_cubes() {
+        let solver = TwoPhaseSolver::new();
+        let result = solver.solve(&Cube::new(4), &Default::default());
+        assert_eq!(result, Err(SolveError::UnsupportedCube));
+    }
+}